@@ -0,0 +1,557 @@
+use failure::Error;
+use stdweb::web::Date;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::OnceLock;
+use yew::format::nothing::Nothing;
+use yew::services::fetch::{Request, Response};
+use regex::Regex;
+
+use crate::model::{CenDashData, Level, Model, Msg};
+use crate::UNGROUPED;
+
+
+/// inventory served as JSON, either plain list of hosts or hosts with groups:
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonInventory {
+    Hosts(Vec<String>),
+    Grouped {
+        #[serde(default)]
+        hosts: Vec<String>,
+        #[serde(default)]
+        groups: BTreeMap<String, Vec<String>>,
+    },
+}
+
+
+impl JsonInventory {
+
+    /// (group, host) pairs, in hosts order, then hosts listed only in groups:
+    fn entries(self) -> Vec<(String, String)> {
+        match self {
+            JsonInventory::Hosts(hosts) => {
+                hosts
+                    .into_iter()
+                    .map(|host| (UNGROUPED.to_string(), host))
+                    .collect()
+            }
+
+            JsonInventory::Grouped { hosts, groups } => {
+                let mut entries = Vec::new();
+                for host in &hosts {
+                    let mut grouped = false;
+                    for (group, members) in &groups {
+                        if members.contains(host) {
+                            entries.push((group.clone(), host.clone()));
+                            grouped = true;
+                        }
+                    }
+                    if !grouped {
+                        entries.push((UNGROUPED.to_string(), host.clone()));
+                    }
+                }
+                for (group, members) in &groups {
+                    for member in members.iter().filter(|member| !hosts.contains(member)) {
+                        entries.push((group.clone(), member.clone()));
+                    }
+                }
+                entries
+            }
+        }
+    }
+
+}
+
+
+/// inventory host lines with their group, from JSON or INI like text:
+pub fn inventory_entries(data: &str) -> Vec<(String, String)> {
+    let trimmed = data.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        // "[group]" header of text inventory isn't valid JSON, falls through:
+        if let Ok(inventory) = serde_json::from_str::<JsonInventory>(trimmed) {
+            return inventory.entries();
+        }
+    }
+
+    let mut group = UNGROUPED.to_string();
+    let mut entries = Vec::new();
+    for line in data.split("\n") {
+        // "[name]" section header starts a new group:
+        if line.len() > 2 && line.starts_with('[') && line.ends_with(']') {
+            group = line[1 .. line.len() - 1].to_string();
+            continue;
+        }
+        if line.is_empty()
+            || line.starts_with('[')
+            || line.ends_with(']')
+            || line.trim_start().starts_with('#')
+            || line.trim_start().starts_with(';') { // comment line
+            continue;
+        }
+        entries.push((group.clone(), line.to_string()));
+    }
+    entries
+}
+
+
+/// hosts of inventory, with their groups, variables and tags:
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inventory {
+
+    // unique kept hosts, in first seen order
+    pub hosts: Vec<String>,
+
+    // count of all host lines, kept or not
+    pub total: usize,
+
+    pub groups: HashMap<String, Vec<String>>,
+
+    pub vars: HashMap<String, HashMap<String, String>>,
+
+    // tags of all hosts, kept or not
+    pub tags: HashMap<String, Vec<String>>,
+
+    // kept hosts listed in more than one group, in first seen order
+    pub duplicates: Vec<String>,
+
+    // (group, line) entries of all host lines, kept or not
+    pub entries: Vec<(String, String)>,
+}
+
+
+impl Inventory {
+
+    /// inventory of (group, line) entries, keeping hosts for which keep(line, host, tags) holds:
+    pub fn from_entries<F>(entries: &[(String, String)], keep: F) -> Inventory
+        where F: Fn(&str, &str, &[String]) -> bool {
+        let mut inventory = Inventory::default();
+        let mut seen = HashSet::new();
+        let mut seen_in_group = HashSet::new();
+        for (group, line) in entries {
+            inventory.total += 1;
+
+            // tags of all hosts are kept, to offer all of them in tag filter:
+            let (host, vars) = parse_host_line(line);
+            let tags
+                = inventory
+                    .tags
+                    .entry(host.clone())
+                    .or_default();
+            for tag in parse_host_tags(line) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            if !keep(line, &host, tags) {
+                continue;
+            }
+
+            inventory
+                .vars
+                .entry(host.clone())
+                .or_default()
+                .extend(vars);
+            // host repeated within its group is listed there once:
+            let repeated = !seen_in_group.insert((group.clone(), host.clone()));
+            if !repeated {
+                inventory
+                    .groups
+                    .entry(group.clone())
+                    .or_default()
+                    .push(host.clone());
+            }
+
+            // host listed in many groups shows up once, in first seen order:
+            if seen.insert(host.clone()) {
+                inventory.hosts.push(host);
+            } else if !repeated && !inventory.duplicates.contains(&host) {
+                inventory.duplicates.push(host);
+            }
+        }
+        inventory
+    }
+
+}
+
+
+/// inventory of raw inventory text, keeping hosts matching filter, not matching exclude and having all picked tags:
+pub fn parse_inventory(raw: &str, data: &CenDashData, filter: &Regex, exclude: Option<&Regex>) -> Inventory {
+    let entries = inventory_entries(raw);
+    let mut inventory
+        = Inventory::from_entries(&entries, |line, host, tags| {
+            data.host_kept(filter, exclude, line, host)
+            && data.tags_picked.iter().all(|tag| tags.contains(tag))
+        });
+    inventory.entries = entries;
+    inventory
+}
+
+
+/// strip terminal escape sequences and control characters of backend provided text.
+/// markup needs no escaping here: yew renders strings as text nodes and attribute values,
+/// and js! passes values as arguments, never as code or HTML:
+pub fn sanitize_text(text: &str) -> String {
+    static ESCAPES: OnceLock<Regex> = OnceLock::new();
+    let escapes = ESCAPES.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap());
+    escapes
+        .replace_all(text, "")
+        .chars()
+        .filter(|character| !character.is_control() || *character == '\n' || *character == '\t')
+        .collect()
+}
+
+
+/// tags of trailing host line comment, like: "# tags: prod,db":
+pub fn parse_host_tags(line: &str) -> Vec<String> {
+    line.find('#')
+        .map(|start| line[start + 1 ..].trim_start())
+        .and_then(|comment| comment.strip_prefix("tags:"))
+        .map(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+
+/// split inventory host line into host name and its key=value variables:
+pub fn parse_host_line(line: &str) -> (String, HashMap<String, String>) {
+    let mut tokens = line.split_whitespace();
+    let host = sanitize_text(tokens.next().unwrap_or_default());
+    let vars
+        = tokens
+            .take_while(|token| !token.starts_with('#')) // trailing comment
+            .filter_map(|token| {
+                let mut pair = token.splitn(2, '=');
+                match (pair.next(), pair.next()) {
+                    (Some(key), Some(value)) if !key.is_empty() => {
+                        Some((key.to_string(), value.trim_matches(|c| c == '"' || c == '\'').to_string()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+    (host, vars)
+}
+
+
+/// host of backend log line prefixed with it, like: "[web01] starting…":
+pub fn parse_log_host(line: &str) -> Option<String> {
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(host, _)| host.trim())
+        .filter(|host| !host.is_empty() && !host.contains(char::is_whitespace))
+        .map(str::to_string)
+}
+
+
+/// host of backend log line reporting it unreachable, like: "host=web1 status=unreachable":
+pub fn parse_unreachable_host(line: &str) -> Option<String> {
+    let mut host = None;
+    let mut unreachable = false;
+    for token in line.split_whitespace() {
+        match token.split_once('=') {
+            Some(("host", value)) => host = Some(value.to_string()),
+            Some(("status", value)) => unreachable = value.eq_ignore_ascii_case("unreachable"),
+            _ => {}
+        }
+    }
+    host.filter(|_| unreachable)
+}
+
+
+/// common prefix of host name, like "web" of "web-01" or "web01", subnet of IPv4 address:
+pub fn host_prefix(host: &str) -> String {
+    if host.parse::<Ipv4Addr>().is_ok() {
+        return host[.. host.rfind('.').unwrap_or_default()].to_string();
+    }
+    let end
+        = host
+            .char_indices()
+            .find(|(index, character)| *character == '-' || (*index > 0 && character.is_ascii_digit()))
+            .map(|(index, _)| index)
+            .unwrap_or(host.len());
+    host[.. end].to_string()
+}
+
+
+/// host names of pasted list, separated by newlines, commas or whitespace:
+pub fn parse_host_list(text: &str) -> Vec<String> {
+    text.split(|character: char| character == ',' || character.is_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+
+impl Model {
+
+    /// fetch inventory, superseding fetch in flight:
+    pub(crate) fn fetch_inventory(&mut self) {
+        let mut request = Request::get(self.data.inventory_url.as_str());
+        if !self.auth_token.is_empty() {
+            request.header("Authorization", format!("Bearer {}", self.auth_token));
+        }
+        let request
+            = match request.body(Nothing) {
+                Ok(request) => request,
+                Err(error) => {
+                    if let Some(mut task) = self.job_onload.take() {
+                        task.cancel();
+                    }
+                    self.inventory_loading = false;
+                    self.backend_healthy = Some(false);
+                    let message = format!("Invalid inventory URL: {}: {}", self.data.inventory_url, error);
+                    self.console.error(&message);
+                    self.push_message(Level::Error, message);
+                    return;
+                }
+            };
+        // responses of superseded fetches are ignored by generation:
+        if let Some(mut task) = self.job_inventory.take() {
+            task.cancel();
+        }
+        self.inventory_generation += 1;
+        let generation = self.inventory_generation;
+        let callback
+            = self
+                .link
+                .send_back(
+                    move |response: Response<Result<String, Error>>| {
+                        let (meta, data) = response.into_parts();
+                        let inventory_data = data.unwrap_or_default();
+                        if meta.status.is_success() {
+                            Msg::InventoryLoaded(generation, inventory_data)
+                        } else {
+                            Msg::InventoryError(generation, meta.status.as_u16())
+                        }
+                    }
+                );
+
+        let handle
+            = self
+                .fetch_service
+                .fetch(request, callback);
+        self
+            .job_inventory = Some(Box::new(handle));
+        self.inventory_loading = true;
+    }
+
+
+    /// probe reachability and latency of inventory endpoint, without loading inventory:
+    pub(crate) fn ping_inventory(&mut self) {
+        let mut request = Request::head(self.data.inventory_url.as_str());
+        if !self.auth_token.is_empty() {
+            request.header("Authorization", format!("Bearer {}", self.auth_token));
+        }
+        let request
+            = match request.body(Nothing) {
+                Ok(request) => request,
+                Err(error) => {
+                    self.notify(format!("Invalid inventory URL: {}: {}", self.data.inventory_url, error));
+                    return;
+                }
+            };
+        let callback
+            = self
+                .link
+                .send_back(
+                    move |response: Response<Result<String, Error>>| {
+                        Msg::PingResult(response.status().as_u16())
+                    }
+                );
+        let handle
+            = self
+                .fetch_service
+                .fetch(request, callback);
+        self.job_ping = Some(Box::new(handle));
+        self.ping_started_at = Date::now() as u64;
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn hosts_of(raw: &str, data: &CenDashData) -> Vec<String> {
+        let filter = data.build_filter(&data.filter_content).unwrap();
+        let exclude = data.build_exclude().unwrap();
+        parse_inventory(raw, data, &filter, exclude.as_ref()).hosts
+    }
+
+
+    #[test]
+    fn parse_inventory_skips_blanks_headers_and_comments() {
+        let raw = "\n[web]\nweb01\n\n# retired\n; old\n[db]\ndb01\n";
+        assert_eq!(hosts_of(raw, &CenDashData::default()), vec!["web01", "db01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_lists_duplicate_hosts_once() {
+        let raw = "[web]\nweb01\nweb01\n[all]\nweb01\n";
+        let data = CenDashData::default();
+        let filter = Regex::new("").unwrap();
+        let inventory = parse_inventory(raw, &data, &filter, None);
+        assert_eq!(inventory.hosts, vec!["web01"]);
+        assert_eq!(inventory.duplicates, vec!["web01"]);
+        assert_eq!(inventory.total, 3);
+        assert_eq!(inventory.groups["web"], vec!["web01"]);
+        assert_eq!(inventory.groups["all"], vec!["web01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_reads_vars_after_hostname() {
+        let raw = "web01 ansible_host=10.0.0.1 port='22' # tags: prod\n";
+        let data = CenDashData::default();
+        let filter = Regex::new("").unwrap();
+        let inventory = parse_inventory(raw, &data, &filter, None);
+        assert_eq!(inventory.hosts, vec!["web01"]);
+        assert_eq!(inventory.vars["web01"]["ansible_host"], "10.0.0.1");
+        assert_eq!(inventory.vars["web01"]["port"], "22");
+        assert_eq!(inventory.tags["web01"], vec!["prod"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_anchored_filter_matches_host_name_only() {
+        let raw = "web01 role=db\ndb01 role=db\n";
+        let mut data = CenDashData { filter_content: "db".to_string(), ..CenDashData::default() };
+        assert_eq!(hosts_of(raw, &data), vec!["web01", "db01"]);
+        data.filter_content = "db.*".to_string();
+        data.filter_anchored = true;
+        assert_eq!(hosts_of(raw, &data), vec!["db01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_keeps_hosts_with_all_picked_tags() {
+        let raw = "web01 # tags: prod,web\nweb02 # tags: web\ndb01 # tags: prod\n";
+        let data = CenDashData { tags_picked: vec!["prod".to_string(), "web".to_string()], ..CenDashData::default() };
+        assert_eq!(hosts_of(raw, &data), vec!["web01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_lists_host_of_two_groups_once() {
+        let raw = "[web]\nweb01\nweb02\n[prod]\nweb01\n";
+        let data = CenDashData::default();
+        let filter = Regex::new("").unwrap();
+        let inventory = parse_inventory(raw, &data, &filter, None);
+        assert_eq!(inventory.hosts, vec!["web01", "web02"]);
+        assert_eq!(inventory.groups["web"], vec!["web01", "web02"]);
+        assert_eq!(inventory.groups["prod"], vec!["web01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_excludes_interleaved_comment_lines() {
+        let raw = "# web servers\nweb01\n; web02 retired\n  # indented comment\nweb03 # trailing comment\n\t; indented\ndb01\n";
+        assert_eq!(hosts_of(raw, &CenDashData::default()), vec!["web01", "web03", "db01"]);
+    }
+
+
+    #[test]
+    fn parse_inventory_of_json_and_text_lists_same_hosts() {
+        let data = CenDashData::default();
+        let filter = Regex::new("").unwrap();
+        let text = parse_inventory("[db]\ndb01\n[web]\nweb01\nweb02\n", &data, &filter, None);
+        let grouped = parse_inventory(r#"{"groups": {"db": ["db01"], "web": ["web01", "web02"]}}"#, &data, &filter, None);
+        let plain = parse_inventory(r#"["db01", "web01", "web02"]"#, &data, &filter, None);
+        assert_eq!(grouped.hosts, text.hosts);
+        assert_eq!(grouped.groups, text.groups);
+        assert_eq!(plain.hosts, text.hosts);
+    }
+
+
+    #[test]
+    fn sanitize_text_keeps_markup_characters_as_text() {
+        // escaping is left to yew text nodes and attributes, characters must survive as they are:
+        assert_eq!(sanitize_text("<script>alert('x')</script>"), "<script>alert('x')</script>");
+        assert_eq!(sanitize_text("web&01\"quoted\""), "web&01\"quoted\"");
+        assert_eq!(sanitize_text("\x1b[31m<web01>\x1b[0m\x07"), "<web01>");
+    }
+
+
+    #[test]
+    fn parse_inventory_of_markup_host_names() {
+        let raw = "\x1b[1m<img src=x>\nweb&01 note=\"a<b\"\n\"quoted\"'host'\n";
+        let data = CenDashData::default();
+        let filter = Regex::new("").unwrap();
+        let inventory = parse_inventory(raw, &data, &filter, None);
+        assert_eq!(inventory.hosts, vec!["<img", "web&01", "\"quoted\"'host'"]);
+        assert_eq!(inventory.vars["web&01"]["note"], "a<b");
+    }
+
+
+    #[test]
+    fn parse_inventory_include_and_exclude_filters() {
+        let raw = "web01\nweb02\ndb01\ndb02\n";
+        let include = CenDashData { filter_content: "web".to_string(), ..CenDashData::default() };
+        assert_eq!(hosts_of(raw, &include), vec!["web01", "web02"]);
+
+        let exclude = CenDashData { exclude_content: "02".to_string(), ..CenDashData::default() };
+        assert_eq!(hosts_of(raw, &exclude), vec!["web01", "db01"]);
+
+        let combined = CenDashData { exclude_content: "02".to_string(), ..include };
+        assert_eq!(hosts_of(raw, &combined), vec!["web01"]);
+    }
+
+
+    // bench, run with: cargo test --lib -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn parse_inventory_of_10k_lines() {
+        use std::time::Instant;
+
+        let raw: String = (0 .. 10_000).map(|index| format!("web{:05} ansible_port=22 # tags: prod\n", index)).collect();
+        let data = CenDashData { filter_content: "web0[0-9]+".to_string(), exclude_content: "7 ".to_string(), ..CenDashData::default() };
+        let started = Instant::now();
+        let hosts = hosts_of(&raw, &data);
+        println!("parsed {} lines into {} hosts in {:?}", 10_000, hosts.len(), started.elapsed());
+        assert_eq!(hosts.len(), 9000);
+    }
+
+
+    #[test]
+    fn parse_log_host_of_prefixed_line() {
+        assert_eq!(parse_log_host("[web01] starting…"), Some("web01".to_string()));
+        assert_eq!(parse_log_host("[ web01 ] starting…"), Some("web01".to_string()));
+        assert_eq!(parse_log_host("[] starting…"), None);
+        assert_eq!(parse_log_host("[web 01] starting…"), None);
+        assert_eq!(parse_log_host("web01 starting…"), None);
+    }
+
+
+    #[test]
+    fn parse_unreachable_host_of_status_line() {
+        assert_eq!(parse_unreachable_host("host=web1 status=unreachable"), Some("web1".to_string()));
+        assert_eq!(parse_unreachable_host("status=UNREACHABLE host=web1"), Some("web1".to_string()));
+        assert_eq!(parse_unreachable_host("host=web1 status=ok"), None);
+        assert_eq!(parse_unreachable_host("status=unreachable"), None);
+    }
+
+
+    #[test]
+    fn host_prefix_of_names_and_addresses() {
+        assert_eq!(host_prefix("web-01"), "web");
+        assert_eq!(host_prefix("web01"), "web");
+        assert_eq!(host_prefix("db"), "db");
+        assert_eq!(host_prefix("10.0.1.17"), "10.0.1");
+    }
+
+
+    #[test]
+    fn parse_host_list_of_mixed_separators() {
+        assert_eq!(parse_host_list("web01, web02\nweb03\tweb04,,"), vec!["web01", "web02", "web03", "web04"]);
+        assert!(parse_host_list(" \n, ").is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-#![recursion_limit = "768"]
+#![recursion_limit = "2048"]
 
 
 #[macro_use]
@@ -8,450 +8,284 @@ extern crate stdweb;
 extern crate serde_derive;
 
 
-use failure::Error;
-use std::time::Duration;
-use yew::format::nothing::Nothing;
-use yew::format::Json;
-use yew::services::{
-    fetch::{FetchService, Request, Response},
-    ConsoleService, IntervalService, Task, StorageService, TimeoutService //, DialogService,
+mod inventory;
+mod model;
+mod update;
+mod view;
+
+
+pub use inventory::{
+    host_prefix, inventory_entries, parse_host_line, parse_host_list, parse_host_tags, parse_inventory, parse_log_host,
+    parse_unreachable_host, sanitize_text, Inventory,
 };
-use yew::{
-    html, ChangeData, Callback, Component, ComponentLink, Html, Renderable, ShouldRender
+pub use model::{
+    csv_field, fuzzy_score, migrate_state, CenDashData, DeployStatus, DeploySummary, HostSort, Level, LogEntry, Model, Msg,
+    WsLine,
 };
-use yew::services::storage::Area;
+
+
 use regex::Regex;
+use std::sync::OnceLock;
+use stdweb::unstable::TryInto;
+use stdweb::web::{Date, Element, EventTarget, IElement};
+use yew::services::storage::Area;
 
 
 const INVENTORY_FILE: &'static str = "/inventory";
 const DATASTORE_BROWSER_ID: &'static str = "cendash-data-store";
+const ENVIRONMENT_BROWSER_ID: &str = "cendash-environment";
+const STORAGE_AREA_BROWSER_ID: &str = "cendash-storage-area";
+const INVENTORY_FETCH_MAX_ATTEMPTS: u32 = 10;
+const FILTER_DEBOUNCE_MS: u64 = 400;
+const UNGROUPED: &str = "ungrouped";
+const MESSAGES_MAX: usize = 500;
+const LOGS_MAX: usize = 5000;
+const WS_BACKOFF_MIN_MS: u64 = 500;
+const WS_BACKOFF_MAX_MS: u64 = 30_000;
+const NOTIFICATION_TIMEOUT_MS: u64 = 3000;
+const INVENTORY_RAW_MAX: usize = 65_536;
+const DEPLOY_INTERVAL_MS: u64 = 300;
+const INVENTORY_POLL_MS: u64 = 500;
+const INTERVAL_MIN_MS: u64 = 50;
+const INTERVAL_MAX_MS: u64 = 10_000;
+const INVENTORY_BACKOFF_MAX_MS: u64 = 30_000;
+const ALL_HOSTS_WARN_MIN: usize = 10;
+const HOST_TIMEOUT_MS: u64 = 60_000;
+const PARALLEL_MAX: usize = 1000;
+const HISTORY_MAX: usize = 10;
+const SCHEMA_VERSION: u32 = 1;
+const HOSTS_SELECT_MIN_SIZE: usize = 5;
+const HOSTS_SELECT_MAX_SIZE: usize = 42;
+const COMMAND_TEMPLATE: &str = "deploy {gitref} --hosts {hosts}";
+const AUTOSAVE_INTERVAL_MS: u64 = 30_000;
+const DEPLOY_HISTORY_MAX: usize = 50;
+const TITLE: &str = "CenDash";
+const PANEL_SELECTOR: &str = ".panel, a, button, input, label, option, select, textarea";
+
+
+/// local time of milliseconds since epoch as HH:MM:SS:
+pub fn format_time(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "--:--:--".to_string(); // unknown, or restored from untimed state
+    }
+    let date = Date::from_time(timestamp as f64);
+    format!("{:02}:{:02}:{:02}", date.get_hours(), date.get_minutes(), date.get_seconds())
+}
 
 
-pub struct Model {
-    link: ComponentLink<Model>,
+/// duration in milliseconds as MM:SS:
+pub fn format_duration(duration_ms: u64) -> String {
+    let seconds = duration_ms / 1000;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
 
-    timeout: TimeoutService,
-    interval: IntervalService,
-    console: ConsoleService,
-    fetch_service: FetchService,
-    local_storage: StorageService,
 
-    callback_deploy: Callback<()>,
-    // callback_done: Callback<()>,
+/// gitref is a full sha1, a short sha or a tag/ branch name:
+pub fn is_valid_gitref(gitref: &str) -> bool {
+    static GITREF: OnceLock<(Regex, Regex, Regex)> = OnceLock::new();
+    let (sha1, short_sha, refname)
+        = GITREF.get_or_init(|| (
+            Regex::new(r"^[0-9a-fA-F]{40}$").unwrap(),
+            Regex::new(r"^[0-9a-fA-F]{7,40}$").unwrap(),
+            Regex::new(r"^[A-Za-z0-9._/-]+$").unwrap(),
+        ));
+    sha1.is_match(gitref)
+    || short_sha.is_match(gitref)
+    || refname.is_match(gitref)
+}
 
-    job: Option<Box<dyn Task>>,
-    job_onload: Option<Box<dyn Task>>,
 
-    // serializable data
-    data: CenDashData,
+/// move value to front of history, without duplicates and capped:
+fn push_history(history: &mut Vec<String>, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    history.retain(|entry| entry != value);
+    history.insert(0, value.to_string());
+    history.truncate(HISTORY_MAX);
 }
 
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct CenDashData {
+/// ISO 8601 UTC time of milliseconds since epoch, empty when unknown:
+fn format_iso_time(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return String::new();
+    }
+    Date::from_time(timestamp as f64).to_iso_string()
+}
 
-    pub gitref: String,
 
-    pub filter_content: String,
+/// command template with {gitref} and comma joined {hosts} substituted:
+fn render_command(template: &str, gitref: &str, hosts: &[String]) -> String {
+    template
+        .replace("{gitref}", gitref)
+        .replace("{hosts}", &hosts.join(","))
+}
 
-    pub messages: Vec<String>,
 
-    pub hosts_all: Vec<String>,
+/// interval in milliseconds clamped to sane bounds:
+fn parse_interval_ms(value: &str) -> Option<u64> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|interval| interval.clamp(INTERVAL_MIN_MS, INTERVAL_MAX_MS))
+}
 
-    pub hosts_picked: Vec<String>,
 
-    pub inventory: Vec<String>,
+/// page is scrolled (nearly) to the very bottom:
+fn is_scrolled_to_bottom() -> bool {
+    let at_bottom = js! {
+        return window.innerHeight + window.pageYOffset >= document.body.scrollHeight - 8;
+    };
+    at_bottom.try_into().unwrap_or(true)
+}
 
-    pub logs: Vec<String>,
 
+/// click target is outside of panels and of controls, which may open them:
+fn is_outside_click(target: Option<EventTarget>) -> bool {
+    target
+        .and_then(|target| target.try_into().ok())
+        .map(|element: Element| element.closest(PANEL_SELECTOR).ok().flatten().is_none())
+        .unwrap_or(false)
 }
 
 
-pub enum Msg {
-    Abort,
-    Done,
-    DeploySteps,
-    Deploy,
-    SetGitRef(String),
-    SetOrUnsetHost(ChangeData),
-    InventoryFetching,
-    InventoryLoad,
-    InventoryLoaded(String),
-    StoreData,
-    RestoreData,
-    SetContentFilter(String),
+/// scroll page to bottom, once pending DOM changes are applied:
+fn scroll_to_bottom() {
+    js! { @(no_return)
+        setTimeout(function() {
+            document.body.scrollIntoView(false);
+        }, 0);
+    }
 }
 
 
-impl Model {
+/// set browser tab title:
+fn set_title(title: &str) {
+    js! { @(no_return)
+        document.title = @{title};
+    }
+}
 
 
-    /// store current state in browser:
-    fn store_state(&mut self) {
-        let data_to_store = Json(&self.data);
-        self
-            .local_storage
-            .store(DATASTORE_BROWSER_ID, data_to_store);
-        self
-            .console
-            .log(&format!("Stored state data"));
+/// apply page colors of light or dark theme:
+fn apply_theme(dark_mode: bool) {
+    let (background, color) = if dark_mode { ("#1e1e1e", "#dcdcdc") } else { ("", "") };
+    js! { @(no_return)
+        document.body.style.background = @{background};
+        document.body.style.color = @{color};
     }
+}
 
 
-    /// load last state from browser:
-    fn restore_state(&mut self) {
-        match self.local_storage.restore(DATASTORE_BROWSER_ID) {
-            Json(Ok(data)) => {
-                self.data = data;
-                self.console.log(&format!("Restored app state!"));
-            },
-
-            Json(Err(_)) => {
-                // self.store_state();
-                // self.data = CenDashData::default();
-                self.console.log(&format!("No app state!"))
-            },
+/// write value to browser storage, false when refused (like when quota is exceeded):
+fn storage_insert(area: &Area, key: &str, value: &str) -> bool {
+    let session = matches!(area, Area::Session);
+    let stored = js! {
+        try {
+            var storage = @{session} ? window.sessionStorage : window.localStorage;
+            storage.setItem(@{key}, @{value});
+            return true;
+        } catch (error) {
+            return false;
         }
-    }
+    };
+    stored.try_into().unwrap_or(false)
+}
 
 
-    /// schedule inventory reloading:
-    fn autoload_inventory(&mut self) -> Option<Box<Task>> {
-        let callback_onload
-            = self
-                .link
-                .send_back(|_| Msg::InventoryLoad);
-        let job_onload
-            = self
-                .interval
-                .spawn(Duration::from_millis(500), callback_onload);
-        Some(Box::new(job_onload))
+/// css class of input by validity of its value:
+fn validity_class(valid: bool) -> &'static str {
+    if valid {
+        "valid"
+    } else {
+        "invalid"
     }
+}
 
 
+/// inline style marking invalid input, no stylesheet is served:
+fn validity_style(valid: bool) -> &'static str {
+    if valid {
+        ""
+    } else {
+        "border: 2px solid red;"
+    }
 }
 
 
-impl Component for Model {
-    type Message = Msg;
-    type Properties = ();
+/// write text to system clipboard:
+fn copy_to_clipboard(text: &str) {
+    js! { @(no_return)
+        navigator.clipboard.writeText(@{text});
+    }
+}
 
-    fn create(_: Self::Properties, mut link: ComponentLink<Self>) -> Self {
-        let mut interval = IntervalService::new();
-        let callback_onload = link.send_back(|_| Msg::InventoryLoad);
-        let job_onload = interval.spawn(Duration::from_secs(0), callback_onload);
 
-        Model {
-            timeout: TimeoutService::new(),
-            fetch_service: FetchService::new(),
-            local_storage: StorageService::new(Area::Local), // or Area::Session
-            console: ConsoleService::new(),
-            callback_deploy: link.send_back(|_| Msg::DeploySteps),
-            // callback_done: link.send_back(|_| Msg::Done),
-            interval,
-            link,
+/// offer text content as file download:
+fn download_file(name: &str, mime: &str, content: &str) {
+    js! { @(no_return)
+        var blob = new Blob([@{content}], { type: @{mime} });
+        var link = document.createElement("a");
+        link.href = URL.createObjectURL(blob);
+        link.download = @{name};
+        document.body.appendChild(link);
+        link.click();
+        document.body.removeChild(link);
+        URL.revokeObjectURL(link.href);
+    }
+}
 
-            job: None,
-            job_onload: Some(Box::new(job_onload)),
 
-            data: CenDashData::default(),
-        }
+/// browser storage key of state of given environment, like: "cendash-data-store-prod":
+fn storage_key(environment: &str) -> String {
+    if environment.is_empty() {
+        DATASTORE_BROWSER_ID.to_string()
+    } else {
+        format!("{}-{}", DATASTORE_BROWSER_ID, environment)
     }
+}
 
 
-    fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        match msg {
-            Msg::InventoryLoad => {
-                let request
-                    = Request::get(INVENTORY_FILE)
-                        .body(Nothing)
-                        .unwrap();
-                let callback
-                    = self
-                        .link
-                        .send_back(
-                            move |response: Response<Result<String, Error>>| {
-                                let (meta, data) = response.into_parts();
-                                let inventory_data = data.unwrap_or_default();
-                                if meta.status.is_success() {
-                                    Msg::InventoryLoaded(inventory_data)
-                                } else {
-                                    Msg::InventoryFetching // not yet fetched
-                                }
-                            }
-                        );
-
-                let handle
-                    = self
-                        .fetch_service
-                        .fetch(request, callback);
-                self
-                    .job = Some(Box::new(handle));
-            }
-
-            Msg::InventoryFetching => {
-                self.console.log("Seeking /static/inventory…");
-            }
-
-            Msg::InventoryLoaded(data) => {
-                self.data.inventory
-                    = data
-                        .split("\n")
-                        .filter(|line| {
-                            let regex = Regex::new(&self.data.filter_content).unwrap();
-                            regex.is_match(&line)
-                            && !line.is_empty()
-                            && !line.starts_with(&"[")
-                            && !line.ends_with(&"]")
-                            && line != &"\n"
-                        })
-                        .map(|line| line.split(" ").take(1).collect::<String>())
-                        .collect();
-                self.data.hosts_all
-                    = self
-                        .data
-                        .inventory
-                        .clone();
-                self.data.hosts_picked
-                    = self
-                        .data
-                        .inventory
-                        .clone();
-
-                self.console.info(&format!("Inventory loaded with {} hosts!", self.data.inventory.len()));
-                self.job = None;
-                self.job_onload = None; // disable job_onload after initial call
-            }
-
-            Msg::Deploy => {
-                if self.data.gitref.len() > 3 { // && self.data.inventory.len() > 0
-                    let handle
-                        = self
-                            .interval
-                            .spawn(Duration::from_millis(300), self.callback_deploy.clone());
-                    self.job = Some(Box::new(handle));
-
-                    self.data.messages.clear();
-                    self.console.clear();
-                    self.console.log(&format!("GitRef: {}", &self.data.gitref));
-                    // self.console.log(&format!("Picked hosts: {:?}", &self.data.hosts_picked));
-
-                } else {
-                    self.data.messages.push(format!("Wrong GitRef given!"));
-                }
-            }
-
-            Msg::Abort => {
-                if let Some(mut task) = self.job.take() {
-                    task.cancel();
-                }
-                self.data.messages.push(format!("Aborted!"));
-                self.console.warn(&format!("Aborted!"));
-                self.store_state();
-                // self.console.assert(self.job.is_none(), "Job still exists!");
-            }
-
-            Msg::Done => {
-                self.data.messages.push(format!("Done!"));
-                self.console.info("Done!");
-                self.store_state();
-                // self.console.group();
-                // self.console.time_named_end("Timer");
-                // self.console.group_end();
-                self.job = None;
-            }
-
-            Msg::DeploySteps => {
-                self.data.messages.push(format!("DeploySteps!"));
-                self.console.count_named(&format!("DeploySteps GitRef: {}", self.data.gitref));
-                self.store_state();
-
-                // // Job's done:
-                // {
-                //     let handle = self
-                //         .timeout
-                //         .spawn(Duration::from_secs(3), self.callback_done.clone());
-                //     self.job = Some(Box::new(handle));
-                // }
-            }
-
-            Msg::SetGitRef(gitref) => {
-                self.data.gitref = gitref.to_string();
-                self.store_state();
-                self.console.log(&format!("SetGitRef: {}", self.data.gitref));
-
-                // reload inventory automatically:
-                self.job_onload = self.autoload_inventory();
-            }
-
-            Msg::SetContentFilter(filter) => {
-                self.data.filter_content = filter.to_string();
-                self.store_state();
-                self.console.log(&format!("SetContentFilter: {}", self.data.filter_content));
-
-                // reload inventory automatically:
-                self.job_onload = self.autoload_inventory();
-            }
-
-            Msg::SetOrUnsetHost(data) => {
-                match data {
-                    ChangeData::Select(hosts) => {
-                        self.data.hosts_picked = hosts.selected_values();
-                        self.store_state();
-                        self.console.log(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
-                    }
-
-                    ChangeData::Value(host) => {
-                        self.console.log(&format!("NoOp for Selected Host: {}", host));
-                    }
-
-                    ChangeData::Files(files) => {
-                        self.console.log(&format!("NoOp for ChangeData::Files(_): {:?}", files));
-                    }
-                }
-            }
-
-            Msg::StoreData => {
-                self.store_state();
-            }
-
-            Msg::RestoreData => {
-                self.restore_state();
-            }
+/// browser storage area of its human readable name, local by default:
+fn storage_area_of_name(name: &str) -> Area {
+    match name {
+        "Session" => Area::Session,
+        _ => Area::Local,
+    }
+}
 
-        }
-        true
+
+/// human readable name of browser storage area:
+fn storage_area_name(area: &Area) -> &'static str {
+    match area {
+        Area::Local => "Local",
+        Area::Session => "Session",
     }
 }
 
-impl Renderable<Model> for Model {
-
-    fn view(&self) -> Html<Self> {
-        let view_message = |message| {
-            html! {
-                <p>
-                    { message }
-                </p>
-            }
-        };
-        let has_job = self.job.is_some();
-
-        let selected_option = |option| {
-            html! {
-                <option selected=true>
-                    { option }
-                </option>
-            }
-        };
-        let unselected_option = |option| {
-            html! {
-                <option selected=false>
-                    { option }
-                </option>
-            }
-        };
-
-        js! {
-            // inject js routine to auto scroll contents to bottom:
-            var element = document.getElementsByTagName("content");
-            element.scrollTop = element.scrollHeight - element.clientHeight;
-            document.body.scrollIntoView(false);
 
-            // focus input box:
-            // document.getElementsByTagName("input").focus();
-            // document.getElementById("input").focus();
-        };
-
-        html! {
-            <article>
-                <span style="display: block; float: left; position: fixed; top: 2em; right: 2em;">
-                    <label>
-                        { "Centra Deployer" }
-                    </label>
-                    <pre>
-                        <input
-                            name="gitref"
-                            size="42"
-                            autofocus=true
-                            required=true
-                            placeholder="Git-ref (tag, branch or sha1)"
-                            value=&self.data.gitref
-                            oninput=|element| Msg::SetGitRef(element.value)
-                        />
-                    </pre>
-                    <pre>
-                        { "Selected: " }
-                        { self.data.hosts_picked.len() }
-                        { " of: " }
-                        { self.data.hosts_all.len() }
-                        { " hosts in total."}
-                    </pre>
-                    <pre>
-                        <label>
-                            { "List of hosts: " }
-                        </label>
-                        <select
-                            name="hosts"
-                            size="42"
-                            required=true
-                            multiple=true
-                            onchange=|option| Msg::SetOrUnsetHost(option)
-                        >
-                            { // handle selected/ unselected items on multi-list
-                                for self.data.hosts_all.iter().map(|option| {
-                                    if self.data.hosts_picked.contains(option) {
-                                        selected_option(option)
-                                    } else {
-                                        unselected_option(option)
-                                    }
-                                })
-                            }
-                        </select>
-                    </pre>
-                    <pre>
-                        <label>
-                            { "Filter hosts: " }
-                        </label>
-                        <input
-                            name="filter_content"
-                            type="find"
-                            size="32"
-                            placeholder="Filter hosts by content"
-                            value=&self.data.filter_content
-                            oninput=|element| Msg::SetContentFilter(element.value)
-                        />
-                    </pre>
-                    <pre>
-                        <button
-                            onclick=|_| Msg::StoreData>{ "Store-State" }
-                        </button>
-                        { "  " }
-                        <button
-                            onclick=|_| Msg::RestoreData>{ "Restore-State" }
-                        </button>
-                    </pre>
-                    <pre>
-                        <button
-                            disabled=has_job
-                            onclick=|_| Msg::Deploy>{ "Deploy!" }
-                        </button>
-                        { "  " }
-                        <button
-                            disabled=!has_job
-                            onclick=|_| Msg::Abort>{ "Abort!" }
-                        </button>
-                    </pre>
-                    <pre>
-                        <button
-                            onclick=|_| Msg::InventoryLoad>{ "Reload-Inventory" }
-                        </button>
-                    </pre>
-                </span>
-
-                <content>
-                    { for self.data.messages.iter().map(view_message) }
-                </content>
-            </article>
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn is_valid_gitref_accepts_shas_and_ref_names() {
+        assert!(is_valid_gitref("3f786850e387550fdab836ed7e6dc881de23001b"));
+        assert!(is_valid_gitref("3f78685"));
+        assert!(is_valid_gitref("feature/login-form_v2.1"));
+        assert!(is_valid_gitref("v1.0.0"));
+    }
+
+
+    #[test]
+    fn is_valid_gitref_rejects_invalid_input() {
+        assert!(!is_valid_gitref(""));
+        assert!(!is_valid_gitref("feature branch"));
+        assert!(!is_valid_gitref("main; rm -rf /"));
+        assert!(!is_valid_gitref("$(whoami)"));
+        assert!(!is_valid_gitref("main\n"));
     }
 }
@@ -0,0 +1,1581 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+use stdweb::web::Date;
+use yew::format::{Binary, Json, Text};
+use yew::services::{
+    fetch::FetchService,
+    reader::{FileData, ReaderService},
+    websocket::{WebSocketService, WebSocketStatus, WebSocketTask},
+    ConsoleService, DialogService, IntervalService, Task, StorageService, TimeoutService,
+};
+use yew::{Callback, ChangeData, ComponentLink};
+use yew::services::storage::Area;
+use regex::{Regex, RegexBuilder};
+
+use crate::inventory::{host_prefix, parse_host_line, parse_log_host, Inventory};
+use crate::{
+    format_iso_time, format_time, is_valid_gitref, render_command, storage_insert, storage_key,
+    ALL_HOSTS_WARN_MIN, COMMAND_TEMPLATE, DEPLOY_HISTORY_MAX, DEPLOY_INTERVAL_MS, HOST_TIMEOUT_MS,
+    FILTER_DEBOUNCE_MS, INVENTORY_FILE, INVENTORY_POLL_MS, LOGS_MAX, MESSAGES_MAX, NOTIFICATION_TIMEOUT_MS, PARALLEL_MAX,
+    SCHEMA_VERSION,
+};
+
+
+pub struct Model {
+    pub(crate) link: ComponentLink<Model>,
+
+    pub(crate) timeout: TimeoutService,
+    pub(crate) interval: IntervalService,
+    pub(crate) console: ConsoleService,
+    pub(crate) dialog: DialogService,
+    pub(crate) fetch_service: FetchService,
+    pub(crate) websocket: WebSocketService,
+    pub(crate) reader: ReaderService,
+    pub(crate) local_storage: StorageService,
+    pub(crate) session_storage: StorageService,
+    pub(crate) storage_area: Area,
+    // environment suffix of storage key, remembered apart from state
+    pub(crate) environment: String,
+
+    pub(crate) callback_deploy: Callback<()>,
+    // callback_done: Callback<()>,
+
+    pub(crate) job: Option<Box<dyn Task>>, // deploy only
+    pub(crate) job_inventory: Option<Box<dyn Task>>,
+    pub(crate) job_onload: Option<Box<dyn Task>>,
+    pub(crate) job_filter: Option<Box<dyn Task>>,
+    pub(crate) job_ws_reconnect: Option<Box<dyn Task>>,
+    pub(crate) job_import: Option<Box<dyn Task>>,
+    pub(crate) job_ping: Option<Box<dyn Task>>,
+    pub(crate) _job_autosave: Box<dyn Task>, // held, never read: dropping it stops autosave
+
+    // deploy log stream
+    pub(crate) ws: Option<WebSocketTask>,
+    pub(crate) ws_backoff_ms: u64,
+
+    // index of next picked host to deploy
+    pub(crate) deploy_step: usize,
+
+    // deploy job is running
+    pub(crate) deploying: bool,
+
+    // index of deploy queue entry being deployed
+    pub(crate) queue_active: Option<usize>,
+
+    // deploy waits for confirmation
+    pub(crate) pending_confirm: bool,
+    pub(crate) all_hosts_acknowledged: bool,
+    // selection to restore after deploy of subset of hosts
+    pub(crate) picked_before_subset: Option<Vec<String>>,
+    pub(crate) deploy_started_at: Option<u64>,
+    // note captured when deploy started
+    pub(crate) deploy_note_running: String,
+    pub(crate) hosts_started_at: HashMap<String, u64>,
+    pub(crate) inventory_generation: u64,
+    // generation of inventory fetch resetting host selection
+    pub(crate) hard_reload_generation: Option<u64>,
+    pub(crate) inventory_backoff_ms: u64,
+    // inventory loaded mid-deploy, applied once deploy ends
+    pub(crate) inventory_deferred: Option<(u64, String)>,
+
+    // unfiltered inventory of last load, for filter preview
+    pub(crate) inventory_entries: Vec<(String, String)>,
+    pub(crate) filter_preview: Option<(usize, usize, usize)>,
+    pub(crate) filter_error: Option<String>,
+    pub(crate) exclude_error: Option<String>,
+
+    // data changed since last store or restore
+    pub(crate) dirty: bool,
+
+    // outcome of last inventory fetch, unknown before first one
+    pub(crate) backend_healthy: Option<bool>,
+    pub(crate) ping_started_at: u64,
+
+    // inventory bearer token, kept in memory only
+    pub(crate) auth_token: String,
+
+    // pasted list of hosts to pick
+    pub(crate) bulk_paste: String,
+
+    // inventory request in flight
+    pub(crate) inventory_loading: bool,
+
+    // deploy log panel expanded
+    pub(crate) logs_visible: bool,
+
+    // deploy history panel expanded
+    pub(crate) deploy_history_visible: bool,
+
+    // host of shown log drill-down
+    pub(crate) host_log_shown: Option<String>,
+
+    // raw inventory panel expanded
+    pub(crate) inventory_raw_visible: bool,
+
+    // hosts grouped by name prefix panel expanded, with its expanded groups
+    pub(crate) prefix_groups_visible: bool,
+    pub(crate) prefix_groups_expanded: HashSet<String>,
+    pub(crate) focused_index: Option<usize>, // focused host of expanded prefix groups
+
+    // contents scroll position follows new messages
+    pub(crate) scrolled_to_bottom: bool,
+    pub(crate) messages_seen: usize,
+
+    // transient notifications with their auto-dismiss jobs
+    pub(crate) notifications: Vec<(u64, String)>,
+    pub(crate) jobs_notifications: HashMap<u64, Box<dyn Task>>,
+    pub(crate) notification_id: u64,
+
+    // last valid compiled content filter
+    pub(crate) filter_regex: Regex,
+    // compiled content filter being edited, none when invalid
+    pub(crate) edited_filter: Option<Regex>,
+    pub(crate) exclude_regex: Option<Regex>,
+    // compiled exclusion filter being edited, none when empty
+    pub(crate) edited_exclude: Result<Option<Regex>, regex::Error>,
+
+    // state before last destructive action
+    pub(crate) undo_snapshot: Option<CenDashData>,
+
+    // serializable data
+    pub(crate) data: CenDashData,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CenDashData {
+
+    pub gitref: String,
+
+    pub filter_content: String,
+
+    pub exclude_content: String,
+
+    pub messages: Vec<LogEntry>,
+
+    pub hosts_all: Vec<String>,
+
+    pub hosts_picked: Vec<String>,
+
+    pub inventory: Vec<String>,
+
+    pub logs: Vec<String>,
+
+    /// log lines prefixed with their host, also kept in logs
+    pub hosts_logs: HashMap<String, Vec<String>>,
+
+    pub inventory_fetch_attempts: u32,
+
+    pub inventory_url: String,
+
+    pub hosts_status: HashMap<String, DeployStatus>,
+
+    pub hosts_groups: HashMap<String, Vec<String>>,
+
+    pub websocket_url: String,
+
+    pub display_filter: String,
+
+    pub dry_run: bool,
+
+    /// deploy without confirmation step
+    pub skip_confirm: bool,
+
+    /// host lines in last loaded inventory, before filtering
+    pub inventory_total: usize,
+
+    pub deploy_interval_ms: u64,
+
+    pub inventory_poll_ms: u64,
+
+    /// connection variables of inventory hosts, like: ansible_host=10.0.0.1
+    pub hosts_vars: HashMap<String, HashMap<String, String>>,
+
+    pub dark_mode: bool,
+
+    /// milliseconds since epoch of last successful inventory load
+    pub inventory_loaded_at: u64,
+
+    pub deploy_queue: Vec<String>,
+
+    pub filter_case_insensitive: bool,
+
+    pub filter_anchored: bool,
+
+    /// last inventory response, as served (capped)
+    pub inventory_raw: String,
+
+    /// informational backend command, with {gitref} and {hosts} placeholders
+    pub command_template: String,
+
+    /// hosts deployed simultaneously
+    pub max_parallel: usize,
+
+    /// recently applied content filters, newest first
+    pub filter_history: Vec<String>,
+
+    /// recently deployed gitrefs, newest first
+    pub gitref_history: Vec<String>,
+
+    pub host_sort: HostSort,
+
+    /// show only error messages
+    pub only_failed: bool,
+
+    /// show only picked hosts in host list
+    pub picked_only: bool,
+
+    /// frequently used hosts, kept even when absent from inventory
+    pub pinned_hosts: Vec<String>,
+
+    /// display filter matches characters in order, not only substrings
+    pub display_fuzzy: bool,
+
+    /// hosts of last finished deploy
+    pub last_deployed_hosts: Vec<String>,
+
+    /// lowest level printed to browser console, warnings and errors are always printed
+    pub log_level: Level,
+
+    /// tags of all inventory hosts, from trailing "# tags: prod,db" comments
+    pub hosts_tags: HashMap<String, Vec<String>>,
+
+    /// hosts must carry all of these tags to be listed
+    pub tags_picked: Vec<String>,
+
+    /// running host exceeding this is marked failed, 0 disables timeout
+    pub host_timeout_ms: u64,
+
+    pub last_deploy_summary: Option<DeploySummary>,
+
+    /// summaries of past deploys, most recent first
+    pub deploy_history: Vec<DeploySummary>,
+
+    /// deploy to first picked hosts only, in inventory order
+    pub limit: Option<usize>,
+
+    /// reason of next deploy, like: "hotfix for CEN-123"
+    pub deploy_note: String,
+
+    /// version of stored state layout, states stored before versioning are 0
+    #[serde(default)]
+    pub schema_version: u32,
+
+}
+
+
+impl CenDashData {
+
+    /// host is shown in the host list under current display filter and picked only toggle:
+    pub fn is_host_shown(&self, host: &str) -> bool {
+        if self.picked_only && !self.hosts_picked.iter().any(|picked| picked == host) {
+            return false;
+        }
+        self.display_filter.is_empty()
+        || if self.display_fuzzy {
+            fuzzy_score(&self.display_filter, host).is_some()
+        } else {
+            host.contains(&self.display_filter)
+        }
+    }
+
+
+    /// regex of given content filter, honoring filter options:
+    pub fn build_filter(&self, filter: &str) -> Result<Regex, regex::Error> {
+        let pattern
+            = if self.filter_anchored && !filter.is_empty() {
+                format!("^(?:{})$", filter)
+            } else {
+                filter.to_string()
+            };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.filter_case_insensitive)
+            .build()
+    }
+
+
+    /// inventory line matches filter, anchored filter matches whole host name:
+    pub fn filter_matches(&self, regex: &Regex, line: &str, host: &str) -> bool {
+        if self.filter_anchored && !self.filter_content.is_empty() {
+            regex.is_match(host)
+        } else {
+            regex.is_match(line)
+        }
+    }
+
+
+    /// regex of exclusion filter, none when it's empty:
+    pub fn build_exclude(&self) -> Result<Option<Regex>, regex::Error> {
+        if self.exclude_content.is_empty() {
+            Ok(None)
+        } else {
+            self.build_filter(&self.exclude_content).map(Some)
+        }
+    }
+
+
+    /// inventory line matches filter and doesn't match exclusion filter:
+    pub fn host_kept(&self, filter: &Regex, exclude: Option<&Regex>, line: &str, host: &str) -> bool {
+        let excluded
+            = exclude.is_some_and(|exclude| {
+                if self.filter_anchored {
+                    exclude.is_match(host)
+                } else {
+                    exclude.is_match(line)
+                }
+            });
+        self.filter_matches(filter, line, host) && !excluded
+    }
+
+
+    /// loaded inventory, all hosts picked or picked hosts still in inventory kept picked:
+    pub fn apply_inventory(&mut self, inventory: &Inventory, pick_all: bool) {
+        self.inventory = inventory.hosts.clone();
+        self.inventory_total = inventory.total;
+        self.hosts_groups = inventory.groups.clone();
+        self.hosts_vars = inventory.vars.clone();
+        self.hosts_tags = inventory.tags.clone();
+        self.hosts_all
+            = self
+                .inventory
+                .clone();
+        if pick_all {
+            self.hosts_picked
+                = self
+                    .inventory
+                    .clone();
+        } else {
+            self.hosts_picked
+                = self
+                    .hosts_all
+                    .iter()
+                    .filter(|host| self.hosts_picked.contains(host))
+                    .cloned()
+                    .collect();
+        }
+    }
+
+
+    /// all tags of inventory hosts and picked tags, sorted:
+    pub fn tag_names(&self) -> Vec<String> {
+        let mut tags: Vec<String>
+            = self
+                .hosts_tags
+                .values()
+                .flatten()
+                .chain(&self.tags_picked) // picked tag gone from inventory can be unpicked
+                .cloned()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+        tags.sort();
+        tags
+    }
+
+
+    /// whole, non trivial inventory picked:
+    pub fn is_deploying_all(&self) -> bool {
+        let targets = self.deploy_targets().len();
+        targets == self.hosts_all.len()
+        && targets > ALL_HOSTS_WARN_MIN
+    }
+
+
+    /// picked hosts deploy goes to, first ones in inventory order when limited:
+    pub fn deploy_targets(&self) -> Vec<String> {
+        match self.limit {
+            Some(limit) if limit < self.hosts_picked.len() => {
+                self.hosts_all
+                    .iter()
+                    .filter(|host| self.hosts_picked.contains(host))
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            }
+            _ => self.hosts_picked.clone(),
+        }
+    }
+
+
+    /// shown hosts in chosen order, stable sort keeps inventory order of equal hosts:
+    pub fn hosts_sorted(&self) -> Vec<&String> {
+        let mut hosts: Vec<&String> = self.hosts_all.iter().filter(|host| self.is_host_shown(host)).collect();
+        match self.host_sort {
+            HostSort::Inventory => {}
+            HostSort::Alphabetic => hosts.sort(),
+            HostSort::PickedFirst => hosts.sort_by_key(|host| !self.hosts_picked.contains(host)),
+        }
+        // best fuzzy matches first:
+        if self.display_fuzzy && !self.display_filter.is_empty() {
+            hosts.sort_by_key(|host| std::cmp::Reverse(fuzzy_score(&self.display_filter, host)));
+        }
+        hosts
+    }
+
+
+    /// summary of deploy to picked hosts:
+    pub fn deploy_summary(&self, aborted: bool, started_at: u64, note: &str) -> DeploySummary {
+        let statuses: Vec<&DeployStatus>
+            = self
+                .hosts_picked
+                .iter()
+                .filter_map(|host| self.hosts_status.get(host))
+                .collect();
+        DeploySummary {
+            gitref: self.gitref.clone(),
+            hosts: self.hosts_picked.len(),
+            succeeded: statuses.iter().filter(|status| ***status == DeployStatus::Success).count(),
+            failed: statuses.iter().filter(|status| matches!(status, DeployStatus::Failed(_) | DeployStatus::Aborted)).count(),
+            aborted,
+            started_at,
+            finished_at: Date::now() as u64,
+            note: note.to_string(),
+        }
+    }
+
+
+    /// deploy history as CSV, most recent first:
+    pub fn deploy_history_csv(&self) -> String {
+        let mut csv = String::from("gitref,hosts,succeeded,failed,aborted,started_at,finished_at,note\n");
+        for summary in &self.deploy_history {
+            csv.push_str(
+                &format!(
+                    "{},{},{},{},{},{},{},{}\n",
+                    csv_field(&summary.gitref),
+                    summary.hosts,
+                    summary.succeeded,
+                    summary.failed,
+                    summary.aborted,
+                    format_iso_time(summary.started_at),
+                    format_iso_time(summary.finished_at),
+                    csv_field(&summary.note),
+                )
+            );
+        }
+        csv
+    }
+
+
+    /// inventory hosts as CSV, with their pick, deploy status and gitref of last deploy covering them:
+    pub fn hosts_csv(&self) -> String {
+        let last_gitref
+            = self
+                .last_deploy_summary
+                .as_ref()
+                .map(|summary| summary.gitref.as_str())
+                .unwrap_or_default();
+        let mut csv = String::from("host,picked,status,last_deploy\n");
+        for host in &self.hosts_all {
+            csv.push_str(
+                &format!(
+                    "{},{},{},{}\n",
+                    csv_field(host),
+                    self.hosts_picked.contains(host),
+                    csv_field(&self.hosts_status.get(host).map(DeployStatus::to_string).unwrap_or_default()),
+                    if self.last_deployed_hosts.contains(host) { csv_field(last_gitref) } else { String::new() },
+                )
+            );
+        }
+        csv
+    }
+
+
+    /// hosts currently being deployed:
+    pub fn in_flight(&self) -> usize {
+        self.hosts_status
+            .values()
+            .filter(|status| **status == DeployStatus::Running)
+            .count()
+    }
+
+
+    /// deploy progress as count of finished picked hosts and total picked hosts:
+    pub fn deploy_progress(&self) -> (usize, usize) {
+        let completed
+            = self
+                .hosts_picked
+                .iter()
+                .filter(|host| self.hosts_status.get(*host).is_some_and(DeployStatus::is_finished))
+                .count();
+        (completed, self.hosts_picked.len())
+    }
+
+
+    /// host variables as "key=value" lines, sorted by key:
+    pub fn host_vars_title(&self, host: &str) -> String {
+        let mut vars
+            = self
+                .hosts_vars
+                .get(host)
+                .map(|vars| {
+                    vars
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect::<Vec<String>>()
+                })
+                .unwrap_or_default();
+        vars.sort();
+        vars.join("\n")
+    }
+
+
+    /// hosts whose last deploy failed, in inventory order:
+    pub fn failed_hosts(&self) -> Vec<String> {
+        self.hosts_all
+            .iter()
+            .filter(|host| matches!(self.hosts_status.get(*host), Some(DeployStatus::Failed(_))))
+            .cloned()
+            .collect()
+    }
+
+
+    /// count of distinct hosts in inventory group:
+    pub fn group_size(&self, group: &str) -> usize {
+        self.hosts_groups
+            .get(group)
+            .map(|hosts| hosts.iter().collect::<HashSet<&String>>().len())
+            .unwrap_or_default()
+    }
+
+
+    /// hosts grouped by common name prefix, in alphabetical order of prefixes:
+    pub fn prefix_groups(&self) -> Vec<(String, Vec<String>)> {
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for host in &self.hosts_all {
+            groups
+                .entry(host_prefix(host))
+                .or_default()
+                .push(host.clone());
+        }
+        groups.into_iter().collect()
+    }
+
+
+    /// hosts of expanded prefix groups, in listed order:
+    pub fn prefix_group_hosts(&self, expanded: &HashSet<String>) -> Vec<String> {
+        self.prefix_groups()
+            .into_iter()
+            .filter(|(prefix, _)| expanded.contains(prefix))
+            .flat_map(|(_, hosts)| hosts)
+            .collect()
+    }
+
+
+    /// pick host, or unpick it when picked already:
+    pub fn toggle_picked(&mut self, host: &str) {
+        let picked = !self.hosts_picked.contains(&host.to_string());
+        self.hosts_picked
+            = self
+                .hosts_all
+                .iter()
+                .filter(|candidate| {
+                    if *candidate == host {
+                        picked
+                    } else {
+                        self.hosts_picked.contains(candidate)
+                    }
+                })
+                .cloned()
+                .collect();
+    }
+
+
+    /// inventory group names in alphabetical order:
+    pub fn group_names(&self) -> Vec<String> {
+        let mut names = self.hosts_groups.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "LogEntryFormat")]
+pub struct LogEntry {
+
+    /// milliseconds since epoch
+    pub timestamp: u64,
+
+    pub message: String,
+
+    pub level: Level,
+
+}
+
+
+/// order of hosts in host list:
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HostSort {
+    #[default]
+    Inventory,
+    Alphabetic,
+    PickedFirst,
+}
+
+
+impl HostSort {
+
+    /// next sort order, cycled by sort button:
+    pub fn next(self) -> HostSort {
+        match self {
+            HostSort::Inventory => HostSort::Alphabetic,
+            HostSort::Alphabetic => HostSort::PickedFirst,
+            HostSort::PickedFirst => HostSort::Inventory,
+        }
+    }
+
+}
+
+
+impl fmt::Display for HostSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostSort::Inventory => write!(f, "inventory"),
+            HostSort::Alphabetic => write!(f, "A-Z"),
+            HostSort::PickedFirst => write!(f, "picked-first"),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Level {
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+
+impl Level {
+
+    /// css class used to render message in the view:
+    pub fn class(self) -> &'static str {
+        match self {
+            Level::Info => "level-info",
+            Level::Warn => "level-warn",
+            Level::Error => "level-error",
+        }
+    }
+
+
+    /// color used to render message in the view:
+    pub fn color(self) -> &'static str {
+        match self {
+            Level::Info => "inherit",
+            Level::Warn => "orange",
+            Level::Error => "red",
+        }
+    }
+
+}
+
+
+/// stored message formats, plain strings come from older app versions:
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LogEntryFormat {
+    Plain(String),
+    Timestamped {
+        timestamp: u64,
+        message: String,
+        #[serde(default)]
+        level: Level,
+    },
+}
+
+
+impl From<LogEntryFormat> for LogEntry {
+    fn from(format: LogEntryFormat) -> Self {
+        match format {
+            LogEntryFormat::Plain(message) => LogEntry { timestamp: 0, message, level: Level::Info },
+            LogEntryFormat::Timestamped { timestamp, message, level } => LogEntry { timestamp, message, level },
+        }
+    }
+}
+
+
+impl LogEntry {
+
+    /// new entry stamped with current browser time:
+    pub fn new(level: Level, message: String) -> Self {
+        LogEntry {
+            timestamp: Date::now() as u64,
+            message,
+            level,
+        }
+    }
+
+
+    /// local time of entry as HH:MM:SS:
+    pub fn time(&self) -> String {
+        format_time(self.timestamp)
+    }
+
+}
+
+
+/// single text line received from deploy log stream:
+pub struct WsLine(Option<String>);
+
+
+impl From<Text> for WsLine {
+    fn from(text: Text) -> Self {
+        WsLine(text.ok())
+    }
+}
+
+
+impl From<Binary> for WsLine {
+    fn from(binary: Binary) -> Self {
+        WsLine(binary.ok().map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeployStatus {
+    Pending,
+    Running,
+    Success,
+    Failed(String),
+    Aborted,
+}
+
+
+impl DeployStatus {
+
+    /// host deploy has completed, successfully or not:
+    pub fn is_finished(&self) -> bool {
+        match self {
+            DeployStatus::Success | DeployStatus::Failed(_) | DeployStatus::Aborted => true,
+            DeployStatus::Pending | DeployStatus::Running => false,
+        }
+    }
+
+
+    /// color used to render status in the view:
+    pub fn color(&self) -> &'static str {
+        match self {
+            DeployStatus::Pending => "gray",
+            DeployStatus::Running => "orange",
+            DeployStatus::Success => "green",
+            DeployStatus::Failed(_) => "red",
+            DeployStatus::Aborted => "purple",
+        }
+    }
+
+}
+
+
+impl fmt::Display for DeployStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeployStatus::Pending => write!(f, "Pending"),
+            DeployStatus::Running => write!(f, "Running"),
+            DeployStatus::Success => write!(f, "Success"),
+            DeployStatus::Failed(reason) => write!(f, "Failed: {}", reason),
+            DeployStatus::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
+
+
+/// outcome of finished or aborted deploy, kept across page reloads:
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploySummary {
+
+    pub gitref: String,
+
+    pub hosts: usize,
+
+    pub succeeded: usize,
+
+    pub failed: usize,
+
+    pub aborted: bool,
+
+    /// milliseconds since epoch
+    #[serde(default)]
+    pub started_at: u64,
+
+    /// milliseconds since epoch
+    pub finished_at: u64,
+
+    #[serde(default)]
+    pub note: String,
+
+}
+
+
+impl fmt::Display for DeploySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Last deploy: gitref {} to {} hosts, {} ok / {} failed at {}{}",
+            self.gitref,
+            self.hosts,
+            self.succeeded,
+            self.failed,
+            format_time(self.finished_at),
+            if self.aborted { " (aborted)" } else { "" }
+        )?;
+        if !self.note.is_empty() {
+            write!(f, ", note: {}", self.note)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl Default for CenDashData {
+    fn default() -> Self {
+        CenDashData {
+            gitref: String::new(),
+            filter_content: String::new(),
+            exclude_content: String::new(),
+            messages: Vec::new(),
+            hosts_all: Vec::new(),
+            hosts_picked: Vec::new(),
+            inventory: Vec::new(),
+            logs: Vec::new(),
+            hosts_logs: HashMap::new(),
+            inventory_fetch_attempts: 0,
+            inventory_url: INVENTORY_FILE.to_string(),
+            hosts_status: HashMap::new(),
+            hosts_groups: HashMap::new(),
+            websocket_url: String::new(),
+            display_filter: String::new(),
+            dry_run: false,
+            skip_confirm: false,
+            inventory_total: 0,
+            deploy_interval_ms: DEPLOY_INTERVAL_MS,
+            inventory_poll_ms: INVENTORY_POLL_MS,
+            hosts_vars: HashMap::new(),
+            dark_mode: false,
+            inventory_loaded_at: 0,
+            deploy_queue: Vec::new(),
+            filter_case_insensitive: false,
+            filter_anchored: false,
+            inventory_raw: String::new(),
+            command_template: COMMAND_TEMPLATE.to_string(),
+            max_parallel: PARALLEL_MAX,
+            filter_history: Vec::new(),
+            gitref_history: Vec::new(),
+            host_sort: HostSort::default(),
+            only_failed: false,
+            picked_only: false,
+            pinned_hosts: Vec::new(),
+            display_fuzzy: false,
+            last_deployed_hosts: Vec::new(),
+            log_level: Level::Info,
+            hosts_tags: HashMap::new(),
+            tags_picked: Vec::new(),
+            host_timeout_ms: HOST_TIMEOUT_MS,
+            last_deploy_summary: None,
+            deploy_history: Vec::new(),
+            limit: None,
+            deploy_note: String::new(),
+            schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+
+pub enum Msg {
+    Abort,
+    Done,
+    DeploySteps,
+    Deploy,
+    RequestDeployConfirm,
+    DeploySingle(String),
+    DeployNewHosts,
+    ToggleDryRun,
+    ToggleSkipConfirm,
+    QueueGitRef,
+    RemoveFromQueue(usize),
+    DeployQueue,
+    RetryFailed,
+    RetryHost(String),
+    CancelDeployConfirm,
+    AcknowledgeAllHosts,
+    SetGitRef(String),
+    SetOrUnsetHost(ChangeData),
+    SelectAllHosts,
+    PinPickedHosts,
+    UnpinHost(String),
+    SelectPinnedHosts,
+    SetBulkPaste(String),
+    ApplyBulkPaste,
+    SelectGroup(ChangeData),
+    SelectTags(ChangeData),
+    DeselectAllHosts,
+    PruneUnreachable(Vec<String>),
+    CopyPickedHosts,
+    CopyDeployCommand,
+    InventoryFetching,
+    InventoryError(u64, u16),
+    InventoryLoad,
+    Autosave,
+    HardReloadInventory,
+    Ping,
+    PingResult(u16),
+    InventoryLoaded(u64, String),
+    StoreData,
+    Undo,
+    ClearMessages,
+    ToggleOnlyFailed,
+    TogglePickedOnly,
+    RestoreData,
+    ResetState,
+    ExportState,
+    ImportState(ChangeData),
+    ImportStateLoaded(FileData),
+    SetContentFilter(String),
+    SetExcludeFilter(String),
+    SetDisplayFilter(String),
+    ApplyContentFilter,
+    SelectFilterHistory(ChangeData),
+    ToggleFilterCaseInsensitive,
+    ToggleDisplayFuzzy,
+    ToggleFilterAnchored,
+    SetInventoryUrl(String),
+    SetEnvironment(ChangeData),
+    SetAuthToken(String),
+    SetWebSocketUrl(String),
+    SetCommandTemplate(String),
+    SetDeployNote(String),
+    SetDeployInterval(String),
+    SetMaxParallel(String),
+    SetHostTimeout(String),
+    SetLimit(String),
+    SetInventoryPoll(String),
+    WsConnect,
+    WsOpened,
+    WsMessage(String),
+    WsClosed,
+    ToggleLogs,
+    ToggleDeployHistory,
+    ExportDeployHistory,
+    ExportHostsCsv,
+    ToggleRawInventory,
+    TogglePrefixGroups,
+    TogglePrefixGroupExpanded(String),
+    TogglePrefixGroupPicked(String),
+    PrefixGroupsKey(String),
+    KeyDown(String, bool),
+    DismissPanel,
+    ToggleHostLog(String),
+    DismissNotification(u64),
+    Scrolled(bool),
+    ScrollToBottom,
+    CopyLogs,
+    ToggleStorageArea,
+    ToggleDarkMode,
+    ToggleVerboseConsole,
+    CycleHostSort,
+}
+
+
+impl Model {
+
+
+    /// storage service of currently selected browser storage area:
+    pub(crate) fn storage(&mut self) -> &mut StorageService {
+        match self.storage_area {
+            Area::Local => &mut self.local_storage,
+            Area::Session => &mut self.session_storage,
+        }
+    }
+
+
+    /// browser storage key of state of current environment:
+    pub(crate) fn storage_key(&self) -> String {
+        storage_key(&self.environment)
+    }
+
+
+    /// store current state in browser, dropping logs when storage is full:
+    pub(crate) fn store_state(&mut self) {
+        let data_to_store
+            = match serde_json::to_string(&self.data) {
+                Ok(json) => json,
+                Err(error) => {
+                    let message = format!("Failed to serialize state: {}", error);
+                    self.console.error(&message);
+                    self.push_message(Level::Error, message);
+                    return;
+                }
+            };
+        if storage_insert(&self.storage_area, &self.storage_key(), &data_to_store) {
+            self.dirty = false;
+            self.trace(&format!("Stored state data ({} bytes)", data_to_store.len()));
+            return;
+        }
+
+        self.console.warn(&format!("Failed to store state data ({} bytes), dropping logs", data_to_store.len()));
+        self.data.logs.clear();
+        self.data.hosts_logs.clear();
+        let stored
+            = serde_json::to_string(&self.data)
+                .map(|json| storage_insert(&self.storage_area, &self.storage_key(), &json))
+                .unwrap_or_default();
+        if stored {
+            self.dirty = false;
+        } else {
+            self.push_message(Level::Error, "Failed to save state (quota exceeded?)".to_string());
+        }
+    }
+
+
+    /// load last state from browser, returns whether there was any:
+    pub(crate) fn restore_state(&mut self) -> bool {
+        let key = self.storage_key();
+        match self.storage().restore(&key) {
+            Json(Ok(stored)) => {
+                let (data, from_version, dropped) = migrate_state(stored);
+                if from_version != SCHEMA_VERSION {
+                    self.trace(&format!("Migrated app state from schema version: {} to: {}", from_version, SCHEMA_VERSION));
+                }
+                if !dropped.is_empty() {
+                    self.console.warn(&format!("Dropped unreadable stored fields: {}", dropped.join(", ")));
+                }
+                self.data = data;
+                self.dirty = false;
+                self.compile_filter();
+                self.notify("Restored app state!".to_string());
+                true
+            },
+
+            Json(Err(_)) => {
+                // self.store_state();
+                // self.data = CenDashData::default();
+                self.notify("No app state!".to_string());
+                false
+            },
+        }
+    }
+
+
+    /// milliseconds since deploy started:
+    pub(crate) fn deploy_elapsed_ms(&self) -> Option<u64> {
+        self.deploy_started_at
+            .map(|started| (Date::now() as u64).saturating_sub(started))
+    }
+
+
+    /// deploy command of current gitref and hosts deploy goes to:
+    pub(crate) fn deploy_command(&self) -> String {
+        render_command(&self.data.command_template, &self.data.gitref, &self.data.deploy_targets())
+    }
+
+
+    /// console trace, printed only at verbose log level:
+    pub(crate) fn trace(&mut self, message: &str) {
+        if self.data.log_level == Level::Info {
+            self.console.log(message);
+        }
+    }
+
+
+    /// show transient notification, dismissed automatically after a while:
+    pub(crate) fn notify(&mut self, message: String) {
+        self.notification_id += 1;
+        let id = self.notification_id;
+        let callback_dismiss
+            = self
+                .link
+                .send_back(move |_| Msg::DismissNotification(id));
+        let handle
+            = self
+                .timeout
+                .spawn(Duration::from_millis(NOTIFICATION_TIMEOUT_MS), callback_dismiss);
+        self.jobs_notifications.insert(id, Box::new(handle));
+        self.notifications.push((id, message));
+    }
+
+
+    /// push timestamped message to visible deploy log:
+    pub(crate) fn push_message(&mut self, level: Level, message: String) {
+        self.data.messages.push(LogEntry::new(level, message));
+        self.dirty = true;
+
+        // drop oldest messages over the limit:
+        if self.data.messages.len() > MESSAGES_MAX {
+            let overflow = self.data.messages.len() - MESSAGES_MAX;
+            self.data.messages.drain(.. overflow);
+        }
+    }
+
+
+    /// append line to deploy log, dropping oldest lines over the limit:
+    pub(crate) fn append_log(&mut self, line: String) {
+        if let Some(host) = parse_log_host(&line) {
+            let host_log
+                = self
+                    .data
+                    .hosts_logs
+                    .entry(host)
+                    .or_default();
+            host_log.push(line.clone());
+            if host_log.len() > LOGS_MAX {
+                let overflow = host_log.len() - LOGS_MAX;
+                host_log.drain(.. overflow);
+            }
+        }
+        self.data.logs.push(line);
+        self.dirty = true;
+        if self.data.logs.len() > LOGS_MAX {
+            let overflow = self.data.logs.len() - LOGS_MAX;
+            self.data.logs.drain(.. overflow);
+        }
+    }
+
+
+    /// open deploy log stream, when WebSocket URL is given:
+    pub(crate) fn ws_connect(&mut self) {
+        if self.data.websocket_url.is_empty() {
+            return;
+        }
+        if !self.data.websocket_url.starts_with("ws://")
+            && !self.data.websocket_url.starts_with("wss://") {
+            let message = format!("Invalid WebSocket URL: {}", self.data.websocket_url);
+            self.console.error(&message);
+            self.push_message(Level::Error, message);
+            return;
+        }
+
+        let callback
+            = self
+                .link
+                .send_back(|line: WsLine| Msg::WsMessage(line.0.unwrap_or_default()));
+        let notification
+            = self
+                .link
+                .send_back(|status| {
+                    match status {
+                        WebSocketStatus::Opened => Msg::WsOpened,
+                        WebSocketStatus::Closed | WebSocketStatus::Error => Msg::WsClosed,
+                    }
+                });
+        let task
+            = self
+                .websocket
+                .connect(&self.data.websocket_url, callback, notification);
+        self.ws = Some(task);
+    }
+
+
+    /// close deploy log stream and drop pending reconnects:
+    pub(crate) fn ws_close(&mut self) {
+        if let Some(mut task) = self.job_ws_reconnect.take() {
+            task.cancel();
+        }
+        if let Some(mut task) = self.ws.take() {
+            task.cancel();
+        }
+    }
+
+
+    /// continue with next deploy queue entry, if deploying a queue:
+    pub(crate) fn deploy_queue_next(&mut self) {
+        match self.queue_active {
+            Some(index) if index + 1 < self.data.deploy_queue.len() => {
+                self.queue_active = Some(index + 1);
+                self.data.gitref = self.data.deploy_queue[index + 1].clone();
+                self.link.send_self(Msg::Deploy);
+            }
+
+            Some(_) => {
+                self.queue_active = None;
+                self.push_message(Level::Info, "Deploy queue finished!".to_string());
+                self.store_state();
+            }
+
+            None => {}
+        }
+    }
+
+
+    /// remember current state for a single level undo:
+    pub(crate) fn snapshot(&mut self) {
+        self.undo_snapshot = Some(self.data.clone());
+    }
+
+
+    /// compile content filter, keeping previous valid regex on failure:
+    pub(crate) fn compile_filter(&mut self) -> bool {
+        match (self.data.build_filter(&self.data.filter_content), self.data.build_exclude()) {
+            (Ok(regex), Ok(exclude)) => {
+                self.edited_filter = Some(regex.clone());
+                self.edited_exclude = Ok(exclude.clone());
+                self.filter_regex = regex;
+                self.exclude_regex = exclude;
+                self.filter_error = None;
+                self.exclude_error = None;
+                true
+            },
+
+            (filter, exclude) => {
+                self.filter_error = filter.as_ref().err().map(|error| error.to_string());
+                self.edited_filter = filter.ok();
+                self.exclude_error = exclude.as_ref().err().map(|error| error.to_string());
+                self.edited_exclude = exclude;
+                let errors: Vec<String>
+                    = self
+                        .filter_error
+                        .iter()
+                        .chain(&self.exclude_error)
+                        .cloned()
+                        .collect();
+                for error in errors {
+                    let message = format!("Invalid filter: {}", error);
+                    self.console.warn(&message);
+                    self.push_message(Level::Warn, message);
+                }
+                false
+            },
+        }
+    }
+
+
+    /// counts of unique hosts kept and excluded by edited filters, out of all hosts of last load:
+    pub(crate) fn preview_filter(&self) -> Option<(usize, usize, usize)> {
+        let regex = self.edited_filter.as_ref()?;
+        let exclude = self.edited_exclude.as_ref().ok()?;
+        let mut all = HashSet::new();
+        let mut matching = HashSet::new();
+        let mut excluded = HashSet::new();
+        for (_, line) in &self.inventory_entries {
+            let (host, _) = parse_host_line(line);
+            if self.data.host_kept(regex, exclude.as_ref(), line, &host) {
+                matching.insert(host.clone());
+            } else if self.data.filter_matches(regex, line, &host) {
+                excluded.insert(host.clone());
+            }
+            all.insert(host);
+        }
+        Some((matching.len(), excluded.len(), all.len()))
+    }
+
+
+    /// reload inventory once user stops typing a filter:
+    pub(crate) fn debounce_filter(&mut self) {
+        if let Some(mut task) = self.job_filter.take() {
+            task.cancel();
+        }
+        let callback_filter
+            = self
+                .link
+                .send_back(|_| Msg::ApplyContentFilter);
+        let handle
+            = self
+                .timeout
+                .spawn(Duration::from_millis(FILTER_DEBOUNCE_MS), callback_filter);
+        self.job_filter = Some(Box::new(handle));
+    }
+
+
+    /// deploy given hosts only, picked hosts are restored after deploy:
+    pub(crate) fn deploy_subset(&mut self, hosts: Vec<String>) {
+        // same guards as Deploy button:
+        if deploy_running(self.deploying, &self.job) || self.pending_confirm {
+            self.notify("Deploy already running!".to_string());
+            return;
+        }
+        if !is_valid_gitref(&self.data.gitref) {
+            self.push_message(Level::Error, "GitRef contains invalid characters".to_string());
+            return;
+        }
+        // hosts absent from inventory can't be deployed:
+        let hosts: Vec<String>
+            = hosts
+                .into_iter()
+                .filter(|host| self.data.hosts_all.contains(host))
+                .collect();
+        if hosts.is_empty() {
+            self.notify("Hosts absent from inventory".to_string());
+            return;
+        }
+        self.picked_before_subset = Some(self.data.hosts_picked.clone());
+        self.data.hosts_picked = hosts;
+        self.link.send_self(Msg::RequestDeployConfirm);
+    }
+
+
+    /// keep summary of finished or aborted deploy as last one, and in deploy history:
+    pub(crate) fn record_deploy(&mut self, aborted: bool) {
+        let summary
+            = self
+                .data
+                .deploy_summary(aborted, self.deploy_started_at.unwrap_or_default(), &self.deploy_note_running);
+        self.data.deploy_history.insert(0, summary.clone());
+        self.data.deploy_history.truncate(DEPLOY_HISTORY_MAX);
+        self.data.last_deploy_summary = Some(summary);
+    }
+
+
+    /// bring back host selection replaced by deploy of subset of hosts:
+    pub(crate) fn restore_picked(&mut self) {
+        if let Some(picked) = self.picked_before_subset.take() {
+            self.data.hosts_picked = picked;
+            self.store_state();
+        }
+    }
+
+
+    /// apply inventory loaded while deploy was running:
+    pub(crate) fn apply_deferred_inventory(&mut self) {
+        if let Some((generation, data)) = self.inventory_deferred.take() {
+            self.link.send_self(Msg::InventoryLoaded(generation, data));
+        }
+    }
+
+
+    /// schedule inventory reloading:
+    pub(crate) fn autoload_inventory(&mut self) -> Option<Box<dyn Task>> {
+        self.data.inventory_fetch_attempts = 0;
+        self.inventory_backoff_ms = self.data.inventory_poll_ms;
+        self.schedule_inventory_load()
+    }
+
+
+    /// schedule single inventory load after current backoff delay:
+    pub(crate) fn schedule_inventory_load(&mut self) -> Option<Box<dyn Task>> {
+        let callback_onload
+            = self
+                .link
+                .send_back(|_| Msg::InventoryLoad);
+        let job_onload
+            = self
+                .timeout
+                .spawn(Duration::from_millis(self.inventory_backoff_ms), callback_onload);
+        Some(Box::new(job_onload))
+    }
+
+
+}
+
+
+/// upgrade stored state of any schema version, field by field.
+/// fields failing to deserialize keep their defaults.
+/// returns state, stored schema version and names of dropped fields:
+pub fn migrate_state(stored: serde_json::Value) -> (CenDashData, u32, Vec<String>) {
+    let from_version
+        = stored
+            .get("schema_version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or_default() as u32;
+
+    // fast path, whole state readable:
+    if let Ok(mut data) = serde_json::from_value::<CenDashData>(stored.clone()) {
+        data.schema_version = SCHEMA_VERSION;
+        return (data, from_version, Vec::new());
+    }
+
+    let mut merged = serde_json::to_value(CenDashData::default()).unwrap_or_default();
+    let mut dropped = Vec::new();
+    if let (Some(fields), Some(merged_fields)) = (stored.as_object(), merged.as_object_mut()) {
+        for (name, value) in fields {
+            let previous = merged_fields.insert(name.clone(), value.clone());
+            let readable = serde_json::from_value::<CenDashData>(serde_json::Value::Object(merged_fields.clone())).is_ok();
+            if !readable {
+                match previous {
+                    Some(previous) => merged_fields.insert(name.clone(), previous),
+                    None => merged_fields.remove(name),
+                };
+                dropped.push(name.clone());
+            }
+        }
+    }
+    let mut data = serde_json::from_value::<CenDashData>(merged).unwrap_or_default();
+    data.schema_version = SCHEMA_VERSION;
+    (data, from_version, dropped)
+}
+
+
+/// case-insensitive subsequence match score of pattern in text, like "wb1" in "web-01".
+/// consecutive characters and characters starting a word score higher:
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in pattern.to_lowercase().chars() {
+        let found
+            = text[position ..]
+                .iter()
+                .position(|character| *character == wanted)?
+                + position;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 2;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 1;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+
+/// CSV field, quoted when it contains separators, quotes or line breaks:
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+
+/// focused index of host list after key press: arrows and Home/ End move focus,
+/// a character jumps to next host starting with it:
+pub fn next_focused_index(hosts: &[String], focused: Option<usize>, key: &str) -> Option<usize> {
+    let last = hosts.len().checked_sub(1)?;
+    let current = focused.map(|index| index.min(last));
+    match key {
+        "ArrowDown" => Some(current.map_or(0, |index| (index + 1).min(last))),
+        "ArrowUp" => Some(current.map_or(0, |index| index.saturating_sub(1))),
+        "Home" => Some(0),
+        "End" => Some(last),
+        _ => {
+            let mut characters = key.chars();
+            let wanted = match (characters.next(), characters.next()) {
+                (Some(character), None) if !character.is_whitespace() => character.to_lowercase().to_string(),
+                _ => return current,
+            };
+            // search wraps around, starting after focused host:
+            let start = current.map_or(0, |index| index + 1);
+            (0 .. hosts.len())
+                .map(|offset| (start + offset) % hosts.len())
+                .find(|index| hosts[*index].to_lowercase().starts_with(&wanted))
+                .or(current)
+        }
+    }
+}
+
+
+/// deploy is running, or its job is left over:
+pub fn deploy_running<T>(deploying: bool, job: &Option<T>) -> bool {
+    deploying || job.is_some()
+}
+
+
+/// start deploy job unless deploy is running already, returns whether it was started:
+pub fn start_deploy<T, F>(deploying: &mut bool, job: &mut Option<T>, spawn: F) -> bool
+where
+    F: FnOnce() -> T,
+{
+    if deploy_running(*deploying, job) {
+        return false;
+    }
+    *job = Some(spawn());
+    *deploying = true;
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::parse_inventory;
+
+
+    #[test]
+    fn two_consecutive_deploys_start_one_job() {
+        let mut deploying = false;
+        let mut job = None;
+        let mut spawned = 0;
+        assert!(start_deploy(&mut deploying, &mut job, || { spawned += 1; spawned }));
+        assert!(deploy_running(deploying, &job));
+        assert!(!start_deploy(&mut deploying, &mut job, || { spawned += 1; spawned }));
+        assert_eq!(spawned, 1);
+        assert_eq!(job, Some(1));
+    }
+
+
+    #[test]
+    fn left_over_deploy_job_blocks_deploy() {
+        let mut deploying = false;
+        let mut job = Some(0);
+        assert!(deploy_running(deploying, &job));
+        assert!(!start_deploy(&mut deploying, &mut job, || 1));
+        assert!(!deploying);
+        assert_eq!(job, Some(0));
+    }
+
+
+    #[test]
+    fn picked_hosts_survive_inventory_reload() {
+        let filter = Regex::new("").unwrap();
+        let mut data = CenDashData::default();
+        data.apply_inventory(&parse_inventory("web01\nweb02\nweb03\ndb01\n", &data, &filter, None), true);
+        assert_eq!(data.hosts_picked, vec!["web01", "web02", "web03", "db01"]);
+
+        data.hosts_picked = vec!["web02".to_string(), "db01".to_string()];
+        data.apply_inventory(&parse_inventory("db01\nweb01\nweb02\nweb04\n", &data, &filter, None), false);
+        assert_eq!(data.hosts_all, vec!["db01", "web01", "web02", "web04"]);
+        assert_eq!(data.hosts_picked, vec!["db01", "web02"]);
+
+        // host gone from inventory is no longer picked:
+        data.apply_inventory(&parse_inventory("web01\nweb02\n", &data, &filter, None), false);
+        assert_eq!(data.hosts_picked, vec!["web02"]);
+    }
+
+
+    #[test]
+    fn next_focused_index_of_arrows_and_typed_characters() {
+        let hosts: Vec<String> = ["db-01", "web-01", "web-02", "worker-01"].iter().map(|host| host.to_string()).collect();
+        assert_eq!(next_focused_index(&hosts, None, "ArrowDown"), Some(0));
+        assert_eq!(next_focused_index(&hosts, Some(0), "ArrowDown"), Some(1));
+        assert_eq!(next_focused_index(&hosts, Some(3), "ArrowDown"), Some(3));
+        assert_eq!(next_focused_index(&hosts, Some(0), "ArrowUp"), Some(0));
+        assert_eq!(next_focused_index(&hosts, Some(2), "Home"), Some(0));
+        assert_eq!(next_focused_index(&hosts, None, "End"), Some(3));
+        assert_eq!(next_focused_index(&hosts, None, "w"), Some(1));
+        assert_eq!(next_focused_index(&hosts, Some(1), "W"), Some(2));
+        assert_eq!(next_focused_index(&hosts, Some(3), "w"), Some(1)); // wraps around
+        assert_eq!(next_focused_index(&hosts, Some(2), "x"), Some(2));
+        assert_eq!(next_focused_index(&hosts, Some(2), "Shift"), Some(2));
+        assert_eq!(next_focused_index(&[], Some(2), "ArrowDown"), None);
+    }
+
+
+    #[test]
+    fn toggle_picked_of_expanded_prefix_group_host() {
+        let hosts: Vec<String> = ["db-01", "web-01", "web-02"].iter().map(|host| host.to_string()).collect();
+        let mut data = CenDashData { hosts_all: hosts.clone(), hosts_picked: hosts, ..CenDashData::default() };
+        let expanded: HashSet<String> = vec!["web".to_string()].into_iter().collect();
+        let listed = data.prefix_group_hosts(&expanded);
+        assert_eq!(listed, vec!["web-01", "web-02"]);
+
+        data.toggle_picked(&listed[0]);
+        assert_eq!(data.hosts_picked, vec!["db-01", "web-02"]);
+        data.toggle_picked(&listed[0]);
+        assert_eq!(data.hosts_picked, vec!["db-01", "web-01", "web-02"]);
+    }
+
+
+    #[test]
+    fn fuzzy_score_prefers_consecutive_characters() {
+        assert!(fuzzy_score("wb1", "web-01").is_some());
+        assert_eq!(fuzzy_score("xyz", "web-01"), None);
+        assert!(fuzzy_score("web", "web-01") > fuzzy_score("web", "w-e-b"));
+        assert_eq!(fuzzy_score("WEB", "web-01"), fuzzy_score("web", "web-01"));
+    }
+
+
+    #[test]
+    fn csv_field_quotes_separators_only() {
+        assert_eq!(csv_field("web01"), "web01");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
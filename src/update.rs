@@ -0,0 +1,1388 @@
+use failure::Error;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use stdweb::traits::{IEvent, IKeyboardEvent};
+use stdweb::web::event::{ClickEvent, KeyDownEvent, ScrollEvent};
+use stdweb::web::{document, window, Date, IEventTarget};
+use yew::services::{
+    fetch::{FetchService, StatusCode},
+    reader::ReaderService,
+    websocket::WebSocketService,
+    ConsoleService, DialogService, IntervalService, StorageService, Task, TimeoutService,
+};
+use yew::{ChangeData, Component, ComponentLink, ShouldRender};
+use yew::services::storage::Area;
+use regex::Regex;
+
+use crate::inventory::{host_prefix, parse_host_list, parse_inventory, parse_unreachable_host, sanitize_text};
+use crate::model::{deploy_running, migrate_state, next_focused_index, start_deploy, CenDashData, DeployStatus, Level, Model, Msg};
+use crate::{
+    copy_to_clipboard, download_file, format_duration, is_outside_click, is_scrolled_to_bottom, is_valid_gitref,
+    parse_interval_ms, push_history, set_title,
+    storage_area_name, storage_area_of_name, storage_insert, AUTOSAVE_INTERVAL_MS, ENVIRONMENT_BROWSER_ID,
+    INVENTORY_BACKOFF_MAX_MS, INVENTORY_FETCH_MAX_ATTEMPTS, INVENTORY_POLL_MS, INVENTORY_RAW_MAX, PARALLEL_MAX,
+    SCHEMA_VERSION, STORAGE_AREA_BROWSER_ID, TITLE, WS_BACKOFF_MAX_MS, WS_BACKOFF_MIN_MS,
+};
+
+
+impl Component for Model {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, mut link: ComponentLink<Self>) -> Self {
+        set_title(TITLE);
+
+        let mut timeout = TimeoutService::new();
+        let callback_onload = link.send_back(|_| Msg::InventoryLoad);
+        let job_onload = timeout.spawn(Duration::from_secs(0), callback_onload);
+
+        // global keyboard shortcuts:
+        let callback_keydown = link.send_back(|(key, ctrl)| Msg::KeyDown(key, ctrl));
+        document().add_event_listener(move |event: KeyDownEvent| {
+            callback_keydown.emit((event.key(), event.ctrl_key() || event.meta_key()));
+        });
+
+        // store changed state periodically, in case browser goes away mid-edit:
+        let mut interval = IntervalService::new();
+        let callback_autosave = link.send_back(|_| Msg::Autosave);
+        let job_autosave = interval.spawn(Duration::from_millis(AUTOSAVE_INTERVAL_MS), callback_autosave);
+
+        // click outside of open panels dismisses them:
+        let callback_click = link.send_back(|_| Msg::DismissPanel);
+        document().add_event_listener(move |event: ClickEvent| {
+            if is_outside_click(event.target()) {
+                callback_click.emit(());
+            }
+        });
+
+        // track whether contents are scrolled to bottom:
+        let callback_scroll = link.send_back(Msg::Scrolled);
+        window().add_event_listener(move |_: ScrollEvent| {
+            callback_scroll.emit(is_scrolled_to_bottom());
+        });
+
+        // environment picked last time in this browser:
+        let local_storage = StorageService::new(Area::Local);
+        let environment: Result<String, Error> = local_storage.restore(ENVIRONMENT_BROWSER_ID);
+        let storage_area: Result<String, Error> = local_storage.restore(STORAGE_AREA_BROWSER_ID);
+
+        let mut model = Model {
+            timeout,
+            fetch_service: FetchService::new(),
+            websocket: WebSocketService::new(),
+            reader: ReaderService::new(),
+            local_storage,
+            session_storage: StorageService::new(Area::Session),
+            storage_area: storage_area_of_name(&storage_area.unwrap_or_default()),
+            environment: environment.unwrap_or_default(),
+            console: ConsoleService::new(),
+            dialog: DialogService::new(),
+            callback_deploy: link.send_back(|_| Msg::DeploySteps),
+            // callback_done: link.send_back(|_| Msg::Done),
+            interval,
+            link,
+
+            job: None,
+            job_inventory: None,
+            job_onload: Some(Box::new(job_onload)),
+            job_filter: None,
+            job_ws_reconnect: None,
+            job_import: None,
+            job_ping: None,
+            _job_autosave: Box::new(job_autosave),
+            ws: None,
+            ws_backoff_ms: WS_BACKOFF_MIN_MS,
+            deploy_step: 0,
+            deploying: false,
+            queue_active: None,
+            pending_confirm: false,
+            all_hosts_acknowledged: false,
+            picked_before_subset: None,
+            deploy_started_at: None,
+            deploy_note_running: String::new(),
+            hosts_started_at: HashMap::new(),
+            inventory_generation: 0,
+            hard_reload_generation: None,
+            inventory_backoff_ms: INVENTORY_POLL_MS,
+            inventory_deferred: None,
+            inventory_entries: Vec::new(),
+            filter_preview: None,
+            filter_error: None,
+            exclude_error: None,
+            dirty: false,
+            backend_healthy: None,
+            ping_started_at: 0,
+            auth_token: String::new(),
+            bulk_paste: String::new(),
+            inventory_loading: true,
+            logs_visible: false,
+            deploy_history_visible: false,
+            host_log_shown: None,
+            inventory_raw_visible: false,
+            prefix_groups_visible: false,
+            prefix_groups_expanded: HashSet::new(),
+            focused_index: None,
+            scrolled_to_bottom: true,
+            messages_seen: 0,
+            notifications: Vec::new(),
+            jobs_notifications: HashMap::new(),
+            notification_id: 0,
+
+            filter_regex: Regex::new("").unwrap(),
+            edited_filter: None,
+            edited_exclude: Ok(None),
+            exclude_regex: None,
+            undo_snapshot: None,
+
+            data: CenDashData::default(),
+        };
+
+        // state of environment picked last time, stored by previous session:
+        model.restore_state();
+        model
+    }
+
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::InventoryLoad => {
+                self.fetch_inventory();
+            }
+
+            Msg::Autosave => {
+                if self.dirty {
+                    self.store_state();
+                }
+                return false;
+            }
+
+            Msg::Ping => {
+                self.ping_inventory();
+            }
+
+            Msg::PingResult(status) => {
+                self.job_ping = None;
+                let latency = (Date::now() as u64).saturating_sub(self.ping_started_at);
+                self.backend_healthy = Some(StatusCode::from_u16(status).is_ok_and(|code| code.is_success()));
+
+                // network failures are reported as 408:
+                let message
+                    = if status == 408 {
+                        "Backend unreachable".to_string()
+                    } else if self.backend_healthy == Some(true) {
+                        format!("Backend OK, {}ms", latency)
+                    } else {
+                        format!("Backend responded HTTP {}, {}ms", status, latency)
+                    };
+                self.notify(message);
+            }
+
+            Msg::HardReloadInventory => {
+                self.snapshot();
+                let generation = self.inventory_generation;
+                self.fetch_inventory();
+                if self.inventory_generation != generation { // fetch started
+                    self.hard_reload_generation = Some(self.inventory_generation);
+                }
+            }
+
+            Msg::InventoryFetching => {
+                self.data.inventory_fetch_attempts += 1;
+                self.trace(&format!("Seeking {} (attempt: {})…", self.data.inventory_url, self.data.inventory_fetch_attempts));
+
+                if self.data.inventory_fetch_attempts >= INVENTORY_FETCH_MAX_ATTEMPTS {
+                    if let Some(mut task) = self.job_onload.take() {
+                        task.cancel();
+                    }
+                    self.job_inventory = None;
+                    self.inventory_loading = false;
+
+                    let message = format!("Inventory fetch failed after {} attempts", self.data.inventory_fetch_attempts);
+                    self.console.error(&message);
+                    self.push_message(Level::Error, message);
+                } else {
+                    // back off exponentially while backend is down:
+                    self.inventory_backoff_ms = (self.inventory_backoff_ms * 2).min(INVENTORY_BACKOFF_MAX_MS);
+                    self.trace(&format!("Next inventory fetch in {}ms", self.inventory_backoff_ms));
+                    self.job_onload = self.schedule_inventory_load();
+                }
+            }
+
+            Msg::InventoryError(generation, _) if generation != self.inventory_generation => {
+                self.trace(&format!("Ignored stale inventory error (generation: {})", generation));
+                return false;
+            }
+
+            Msg::InventoryError(_, status) => {
+                self.backend_healthy = Some(false);
+                let reason
+                    = StatusCode::from_u16(status)
+                        .ok()
+                        .and_then(|code| code.canonical_reason())
+                        .unwrap_or_default();
+                let message
+                    = if status == 401 {
+                        "Unauthorized — check token".to_string()
+                    } else {
+                        format!("Inventory fetch failed: HTTP {} {}", status, reason)
+                    };
+
+                // server errors, timeouts and network failures (reported as 408) are transient:
+                if status >= 500 || status == 408 || status == 429 {
+                    self.console.warn(&message);
+                    self.link.send_self(Msg::InventoryFetching); // not yet fetched
+                } else {
+                    if let Some(mut task) = self.job_onload.take() {
+                        task.cancel();
+                    }
+                    self.job_inventory = None;
+                    self.inventory_loading = false;
+                    self.console.error(&message);
+                    self.push_message(Level::Error, message);
+                }
+            }
+
+            Msg::InventoryLoaded(generation, _) if generation != self.inventory_generation => {
+                self.trace(&format!("Ignored stale inventory (generation: {})", generation));
+                return false;
+            }
+
+            Msg::InventoryLoaded(generation, data) if self.deploying => {
+                // deploy steps over picked hosts, so they change only once it ends:
+                self.trace(&format!("Inventory loaded mid-deploy, deferred (generation: {})", generation));
+                self.inventory_deferred = Some((generation, data));
+                self.job_inventory = None;
+                self.inventory_loading = false;
+                return false;
+            }
+
+            Msg::InventoryLoaded(generation, data) => {
+                self.backend_healthy = Some(true);
+                let inventory = parse_inventory(&data, &self.data, &self.filter_regex, self.exclude_regex.as_ref());
+                // pick all hosts on very first load and hard reload:
+                let hard_reload = self.hard_reload_generation.take() == Some(generation);
+                let pick_all = self.data.inventory_loaded_at == 0 || hard_reload;
+                self.data.apply_inventory(&inventory, pick_all);
+                if !inventory.duplicates.is_empty() {
+                    let message = format!("Hosts listed in many groups: {}", inventory.duplicates.join(", "));
+                    self.console.warn(&message);
+                    self.push_message(Level::Warn, message);
+                }
+                self.data.inventory_raw = data.chars().take(INVENTORY_RAW_MAX).collect();
+                self.inventory_entries = inventory.entries;
+                self.filter_preview = None;
+                self.data.inventory_loaded_at = Date::now() as u64;
+
+                self.data.inventory_fetch_attempts = 0;
+                self.inventory_backoff_ms = self.data.inventory_poll_ms;
+
+                if self.data.inventory.is_empty() {
+                    let message = format!("Filter '{}' excluding '{}' matched 0 hosts out of {} lines", self.data.filter_content, self.data.exclude_content, self.data.inventory_total);
+                    self.console.warn(&message);
+                    self.push_message(Level::Warn, message);
+                }
+                self.trace(&format!("Inventory loaded with {} hosts!", self.data.inventory.len()));
+                self.job_inventory = None;
+                self.job_onload = None; // disable job_onload after initial call
+                self.inventory_loading = false;
+            }
+
+            Msg::DeploySingle(host) => {
+                self.deploy_subset(vec![host]);
+            }
+
+            Msg::DeployNewHosts => {
+                let hosts: Vec<String>
+                    = self
+                        .data
+                        .hosts_picked
+                        .iter()
+                        .filter(|host| !self.data.last_deployed_hosts.contains(host))
+                        .cloned()
+                        .collect();
+                if hosts.is_empty() {
+                    self.notify("No new hosts since last deploy".to_string());
+                    return true;
+                }
+                self.deploy_subset(hosts);
+            }
+
+            Msg::RequestDeployConfirm => {
+                if self.data.dry_run {
+                    self.link.send_self(Msg::Deploy); // preview needs no confirmation
+                } else if self.data.skip_confirm {
+                    self.link.send_self(Msg::Deploy); // deploy to all hosts still requires acknowledgement
+                } else {
+                    self.pending_confirm = true;
+                }
+            }
+
+            Msg::ToggleDryRun => {
+                self.data.dry_run = !self.data.dry_run;
+                self.store_state();
+            }
+
+            Msg::ToggleSkipConfirm => {
+                self.data.skip_confirm = !self.data.skip_confirm;
+                self.store_state();
+            }
+
+            Msg::CancelDeployConfirm => {
+                self.pending_confirm = false;
+                self.all_hosts_acknowledged = false;
+                self.queue_active = None;
+                self.restore_picked();
+            }
+
+            Msg::AcknowledgeAllHosts => {
+                self.all_hosts_acknowledged = !self.all_hosts_acknowledged;
+            }
+
+            Msg::QueueGitRef => {
+                if is_valid_gitref(&self.data.gitref) {
+                    self.data.deploy_queue.push(self.data.gitref.clone());
+                    self.store_state();
+                } else {
+                    self.notify(format!("Can't queue invalid GitRef: {}", self.data.gitref));
+                }
+            }
+
+            Msg::RemoveFromQueue(index) => {
+                if self.queue_active.is_none() && index < self.data.deploy_queue.len() {
+                    self.data.deploy_queue.remove(index);
+                    self.store_state();
+                }
+            }
+
+            Msg::RetryFailed => {
+                // confirmed like any deploy, picked hosts are restored after it:
+                if !self.deploying {
+                    let failed = self.data.failed_hosts();
+                    if !failed.is_empty() {
+                        self.deploy_subset(failed);
+                    }
+                }
+            }
+
+            Msg::RetryHost(host) => {
+                // deploy resets status of just this host to pending, others keep theirs:
+                if !self.deploying {
+                    self.deploy_subset(vec![host]);
+                }
+            }
+
+            Msg::DeployQueue => {
+                if !self.deploying && !self.data.deploy_queue.is_empty() {
+                    self.queue_active = Some(0);
+                    self.data.gitref = self.data.deploy_queue[0].clone();
+                    self.link.send_self(Msg::RequestDeployConfirm);
+                }
+            }
+
+            Msg::Deploy => {
+                // guard against double-clicks queued before re-render disables Deploy:
+                if deploy_running(self.deploying, &self.job) {
+                    self.console.warn("Deploy already running!");
+                    return false;
+                }
+                if self.data.hosts_picked.is_empty() {
+                    self.pending_confirm = false;
+                    self.queue_active = None;
+                    self.push_message(Level::Error, "Select at least one host".to_string());
+                    return true;
+                }
+                // deploy to whole inventory requires explicit acknowledgement:
+                if !self.data.dry_run && self.data.is_deploying_all() && !self.all_hosts_acknowledged {
+                    self.pending_confirm = true;
+                    self.notify(format!("Acknowledge deploy to ALL {} hosts first", self.data.hosts_picked.len()));
+                    return true;
+                }
+                self.pending_confirm = false;
+                self.all_hosts_acknowledged = false;
+
+                // limited deploy, picked hosts are restored after deploy:
+                let targets = self.data.deploy_targets();
+                if targets.len() < self.data.hosts_picked.len() {
+                    self.push_message(Level::Info, format!("Deploy limited to {} of {} picked hosts", targets.len(), self.data.hosts_picked.len()));
+                    if self.picked_before_subset.is_none() {
+                        self.picked_before_subset = Some(self.data.hosts_picked.clone());
+                    }
+                    self.data.hosts_picked = targets;
+                }
+
+                if is_valid_gitref(&self.data.gitref) && self.data.dry_run {
+                    self.push_message(Level::Info, format!("Dry-run: would deploy GitRef: {} to {} hosts:", self.data.gitref, self.data.hosts_picked.len()));
+                    for host in self.data.hosts_picked.clone() {
+                        self.push_message(Level::Info, format!("Dry-run: would deploy to: {}", host));
+                    }
+                    self.restore_picked();
+                    self.store_state();
+                    self.deploy_queue_next();
+
+                } else if is_valid_gitref(&self.data.gitref) { // && self.data.inventory.len() > 0
+                    let interval = &mut self.interval;
+                    let callback_deploy = self.callback_deploy.clone();
+                    let deploy_interval = Duration::from_millis(self.data.deploy_interval_ms);
+                    let spawn_deploy = || Box::new(interval.spawn(deploy_interval, callback_deploy)) as Box<dyn Task>;
+                    if !start_deploy(&mut self.deploying, &mut self.job, spawn_deploy) {
+                        self.restore_picked();
+                        self.console.warn("Deploy already running!");
+                        return false;
+                    }
+                    self.snapshot();
+                    push_history(&mut self.data.gitref_history, &self.data.gitref.clone());
+                    self.deploy_started_at = Some(Date::now() as u64);
+                    self.deploy_note_running = self.data.deploy_note.trim().to_string();
+                    self.deploy_step = 0;
+                    self.ws_backoff_ms = WS_BACKOFF_MIN_MS;
+                    self.ws_close();
+                    self.ws_connect();
+
+                    // keep messages of earlier deploy queue entries:
+                    if self.queue_active.unwrap_or_default() == 0 {
+                        self.data.messages.clear();
+                        self.data.logs.clear();
+                        self.data.hosts_logs.clear();
+                    }
+                    let command = self.deploy_command();
+                    self.append_log(format!("$ {}", command));
+
+                    // hosts outside of this deploy keep their last known status:
+                    for host in self.data.hosts_picked.clone() {
+                        self.data.hosts_status.insert(host, DeployStatus::Pending);
+                    }
+                    self.console.clear();
+                    self.trace(&format!("GitRef: {}", &self.data.gitref));
+                    // self.trace(&format!("Picked hosts: {:?}", &self.data.hosts_picked));
+
+                } else if self.data.gitref.trim().is_empty() {
+                    self.queue_active = None;
+                    self.restore_picked();
+                    self.push_message(Level::Error, "Wrong GitRef given!".to_string());
+                } else {
+                    self.queue_active = None;
+                    self.restore_picked();
+                    self.push_message(Level::Error, "GitRef contains invalid characters".to_string());
+                }
+            }
+
+            Msg::Abort => {
+                let job = self.job.take();
+                let job_onload = self.job_onload.take();
+                let job_inventory = self.job_inventory.take();
+                // stuck deploy without job is still cleared below:
+                if job.is_none() && job_onload.is_none() && job_inventory.is_none() && !self.deploying {
+                    self.notify("Nothing running".to_string());
+                    return true;
+                }
+                for mut task in job.into_iter().chain(job_onload).chain(job_inventory) {
+                    task.cancel();
+                }
+                // summary of partial deploy, cancelled hosts are marked as aborted:
+                let message
+                    = if self.deploying {
+                        let (mut done, mut running, mut pending) = (0, 0, 0);
+                        for host in &self.data.hosts_picked {
+                            match self.data.hosts_status.get_mut(host) {
+                                Some(status @ DeployStatus::Running) => {
+                                    *status = DeployStatus::Aborted;
+                                    running += 1;
+                                }
+                                Some(DeployStatus::Pending) => pending += 1,
+                                Some(status) if status.is_finished() => done += 1,
+                                _ => {}
+                            }
+                        }
+                        self.record_deploy(true);
+                        format!("Aborted: {} done, {} running (cancelled), {} pending", done, running, pending)
+                    } else {
+                        "Aborted!".to_string()
+                    };
+                self.inventory_loading = false;
+                self.deploying = false;
+                self.apply_deferred_inventory();
+                self.queue_active = None;
+                self.restore_picked();
+                self.ws_close();
+                self.push_message(Level::Warn, message);
+                if let Some(elapsed) = self.deploy_elapsed_ms() {
+                    self.push_message(Level::Warn, format!("Deploy aborted after {}", format_duration(elapsed)));
+                    self.deploy_started_at = None;
+                }
+                self.console.warn(&format!("Aborted!"));
+                set_title(TITLE);
+                self.store_state();
+                // self.console.assert(self.job.is_none(), "Job still exists!");
+            }
+
+            Msg::Done => {
+                if self.deploy_note_running.is_empty() {
+                    self.push_message(Level::Info, "Done!".to_string());
+                } else {
+                    self.push_message(Level::Info, format!("Done! Note: {}", self.deploy_note_running));
+                }
+                self.record_deploy(false);
+                if let Some(elapsed) = self.deploy_elapsed_ms() {
+                    self.push_message(Level::Info, format!("Deploy finished in {}", format_duration(elapsed)));
+                    self.deploy_started_at = None;
+                }
+                self.trace("Done!");
+                set_title(TITLE);
+                self.data.last_deployed_hosts = self.data.hosts_picked.clone();
+                self.store_state();
+                // self.console.group();
+                // self.console.time_named_end("Timer");
+                // self.console.group_end();
+                if let Some(mut task) = self.job.take() {
+                    task.cancel();
+                }
+                self.deploying = false;
+                self.apply_deferred_inventory();
+                self.ws_close();
+                self.restore_picked();
+                self.deploy_queue_next();
+            }
+
+            Msg::WsConnect => {
+                self.job_ws_reconnect = None;
+                self.ws_connect();
+            }
+
+            Msg::WsOpened => {
+                self.ws_backoff_ms = WS_BACKOFF_MIN_MS;
+                self.trace(&format!("Connected to: {}", self.data.websocket_url));
+            }
+
+            Msg::WsMessage(line) => {
+                let line = sanitize_text(&line);
+                if let Some(host) = parse_unreachable_host(&line) {
+                    self.link.send_self(Msg::PruneUnreachable(vec![host]));
+                }
+                if !line.is_empty() {
+                    self.append_log(line);
+                }
+            }
+
+            Msg::KeyDown(key, ctrl) => {
+                // shortcuts respect the same guards as Deploy/ Abort buttons:
+                let has_job = self.job.is_some();
+                match (key.as_str(), ctrl) {
+                    ("Enter", true) if !has_job && self.pending_confirm => {
+                        self.link.send_self(Msg::Deploy);
+                    }
+
+                    ("Enter", true) if !has_job => {
+                        self.link.send_self(Msg::RequestDeployConfirm);
+                    }
+
+                    ("Escape", _) if has_job => {
+                        self.link.send_self(Msg::Abort);
+                    }
+
+                    ("Escape", _) if self.pending_confirm => {
+                        self.link.send_self(Msg::CancelDeployConfirm);
+                    }
+
+                    // running deploy is aborted and confirmation cancelled first:
+                    ("Escape", _) => {
+                        self.link.send_self(Msg::DismissPanel);
+                    }
+
+                    _ => return false,
+                }
+            }
+
+            Msg::ToggleHostLog(host) => {
+                if self.host_log_shown.as_ref() == Some(&host) {
+                    self.host_log_shown = None;
+                } else {
+                    self.host_log_shown = Some(host);
+                }
+            }
+
+            Msg::DismissPanel => {
+                if self.pending_confirm {
+                    self.link.send_self(Msg::CancelDeployConfirm);
+                    return false;
+                } else if self.inventory_raw_visible {
+                    self.inventory_raw_visible = false;
+                } else if self.host_log_shown.is_some() {
+                    self.host_log_shown = None;
+                } else {
+                    return false; // most clicks find no panel open
+                }
+            }
+
+            Msg::DismissNotification(id) => {
+                if let Some(mut task) = self.jobs_notifications.remove(&id) {
+                    task.cancel();
+                }
+                self.notifications.retain(|(notification_id, _)| *notification_id != id);
+            }
+
+            Msg::Scrolled(at_bottom) => {
+                if at_bottom == self.scrolled_to_bottom {
+                    return false;
+                }
+                self.scrolled_to_bottom = at_bottom;
+                self.messages_seen = self.data.messages.len();
+            }
+
+            Msg::ScrollToBottom => {
+                self.scrolled_to_bottom = true;
+            }
+
+            Msg::ToggleRawInventory => {
+                self.inventory_raw_visible = !self.inventory_raw_visible;
+            }
+
+            Msg::TogglePrefixGroups => {
+                self.prefix_groups_visible = !self.prefix_groups_visible;
+            }
+
+            Msg::TogglePrefixGroupExpanded(prefix) => {
+                if !self.prefix_groups_expanded.remove(&prefix) {
+                    self.prefix_groups_expanded.insert(prefix);
+                }
+            }
+
+            Msg::TogglePrefixGroupPicked(prefix) => {
+                // pick whole group, or unpick it when all its hosts are picked already:
+                let all_picked
+                    = self
+                        .data
+                        .hosts_all
+                        .iter()
+                        .filter(|host| host_prefix(host) == prefix)
+                        .all(|host| self.data.hosts_picked.contains(host));
+                self.data.hosts_picked
+                    = self
+                        .data
+                        .hosts_all
+                        .iter()
+                        .filter(|host| {
+                            if host_prefix(host) == prefix {
+                                !all_picked
+                            } else {
+                                self.data.hosts_picked.contains(host)
+                            }
+                        })
+                        .cloned()
+                        .collect();
+                self.store_state();
+                self.trace(&format!("Prefix: {} Hosts Selected: {}", prefix, self.data.hosts_picked.len()));
+            }
+
+            Msg::PrefixGroupsKey(key) => {
+                let hosts = self.data.prefix_group_hosts(&self.prefix_groups_expanded);
+                if key == " " {
+                    match self.focused_index.and_then(|index| hosts.get(index)) {
+                        Some(host) => self.data.toggle_picked(host),
+                        None => return false,
+                    }
+                    self.store_state();
+                } else {
+                    let focused = next_focused_index(&hosts, self.focused_index, &key);
+                    if focused == self.focused_index {
+                        return false;
+                    }
+                    self.focused_index = focused;
+                }
+            }
+
+            Msg::ToggleLogs => {
+                self.logs_visible = !self.logs_visible;
+            }
+
+            Msg::CopyLogs => {
+                copy_to_clipboard(&self.data.logs.join("\n"));
+                self.trace(&format!("Copied {} log lines", self.data.logs.len()));
+            }
+
+            Msg::WsClosed => {
+                // stream is dropped by ws_close() on intentional close:
+                if self.ws.take().is_some() && self.job.is_some() {
+                    self.console.warn(&format!("Deploy log stream closed, reconnecting in {}ms…", self.ws_backoff_ms));
+                    let callback_reconnect
+                        = self
+                            .link
+                            .send_back(|_| Msg::WsConnect);
+                    let handle
+                        = self
+                            .timeout
+                            .spawn(Duration::from_millis(self.ws_backoff_ms), callback_reconnect);
+                    self.job_ws_reconnect = Some(Box::new(handle));
+                    self.ws_backoff_ms = (self.ws_backoff_ms * 2).min(WS_BACKOFF_MAX_MS);
+                }
+            }
+
+            Msg::DeploySteps => {
+                // fail hosts running for too long:
+                if self.data.host_timeout_ms > 0 {
+                    let now = Date::now() as u64;
+                    let timed_out: Vec<String>
+                        = self
+                            .data
+                            .hosts_status
+                            .iter()
+                            .filter(|(_, status)| **status == DeployStatus::Running)
+                            .filter(|(host, _)| {
+                                self.hosts_started_at
+                                    .get(*host)
+                                    .is_some_and(|started| now.saturating_sub(*started) > self.data.host_timeout_ms)
+                            })
+                            .map(|(host, _)| host.clone())
+                            .collect();
+                    for host in timed_out {
+                        let status = DeployStatus::Failed("timeout".to_string());
+                        self.push_message(Level::Error, format!("Host {} timed out after {}ms", host, self.data.host_timeout_ms));
+                        self.append_log(format!("gitref={} host={} status={}", self.data.gitref, host, status));
+                        self.data.hosts_status.insert(host, status);
+                    }
+                }
+
+                // finish hosts started in previous step:
+                let mut finished = Vec::new();
+                for (host, status) in self.data.hosts_status.iter_mut() {
+                    if *status == DeployStatus::Running {
+                        *status = DeployStatus::Success;
+                        finished.push(host.clone());
+                    }
+                }
+                for host in finished {
+                    self.append_log(format!("gitref={} host={} status={}", self.data.gitref, host, DeployStatus::Success));
+                }
+
+                // start next hosts up to parallel limit:
+                while self.data.in_flight() < self.data.max_parallel {
+                    match self.data.hosts_picked.get(self.deploy_step).cloned() {
+                        Some(host) => {
+                            self.append_log(format!("gitref={} host={} status={}", self.data.gitref, host, DeployStatus::Running));
+                            self.hosts_started_at.insert(host.clone(), Date::now() as u64);
+                            self.data.hosts_status.insert(host, DeployStatus::Running);
+                            self.deploy_step += 1;
+                        }
+
+                        None => break,
+                    }
+                }
+
+                // progress shown in tab title, to follow deploy in background tab:
+                let (completed, total) = self.data.deploy_progress();
+                set_title(&format!("Deploying {}/{} — {}", completed, total, TITLE));
+
+                // finish when all picked hosts were processed:
+                if self.data.in_flight() == 0 {
+                    self.link.send_self(Msg::Done);
+                }
+
+                if self.data.log_level == Level::Info {
+                    self.console.count_named(&format!("DeploySteps GitRef: {}", self.data.gitref));
+                }
+                self.store_state();
+            }
+
+            Msg::SetGitRef(gitref) => {
+                self.data.gitref = gitref.to_string();
+                self.store_state();
+                self.trace(&format!("SetGitRef: {}", self.data.gitref));
+
+                // reload inventory automatically:
+                self.job_onload = self.autoload_inventory();
+            }
+
+            Msg::SetContentFilter(filter) => {
+                if filter == self.data.filter_content {
+                    return false;
+                }
+                // snapshot once, when user starts editing the filter:
+                if self.job_filter.is_none() {
+                    self.snapshot();
+                }
+                self.data.filter_content = filter.to_string();
+                self.store_state();
+                self.trace(&format!("SetContentFilter: {}", self.data.filter_content));
+
+                // compiled once per change, then reused by preview:
+                match self.data.build_filter(&self.data.filter_content) {
+                    Ok(regex) => {
+                        self.edited_filter = Some(regex);
+                        self.filter_error = None;
+                    }
+
+                    Err(error) => {
+                        self.edited_filter = None;
+                        self.filter_error = Some(error.to_string());
+                    }
+                }
+                self.filter_preview = self.preview_filter();
+                self.debounce_filter();
+            }
+
+            Msg::SetExcludeFilter(exclude) => {
+                if exclude == self.data.exclude_content {
+                    return false;
+                }
+                // snapshot once, when user starts editing the filter:
+                if self.job_filter.is_none() {
+                    self.snapshot();
+                }
+                self.data.exclude_content = exclude.to_string();
+                self.store_state();
+                self.trace(&format!("SetExcludeFilter: {}", self.data.exclude_content));
+
+                // compiled once per change, then reused by preview:
+                self.edited_exclude = self.data.build_exclude();
+                self.exclude_error
+                    = self
+                        .edited_exclude
+                        .as_ref()
+                        .err()
+                        .map(|error| error.to_string());
+                self.filter_preview = self.preview_filter();
+                self.debounce_filter();
+            }
+
+            Msg::SetDisplayFilter(filter) => {
+                self.data.display_filter = filter;
+                self.store_state();
+            }
+
+            Msg::ToggleDisplayFuzzy => {
+                self.data.display_fuzzy = !self.data.display_fuzzy;
+                self.store_state();
+            }
+
+            Msg::ToggleFilterCaseInsensitive => {
+                self.data.filter_case_insensitive = !self.data.filter_case_insensitive;
+                self.store_state();
+                self.link.send_self(Msg::ApplyContentFilter);
+            }
+
+            Msg::ToggleFilterAnchored => {
+                self.data.filter_anchored = !self.data.filter_anchored;
+                self.store_state();
+                self.link.send_self(Msg::ApplyContentFilter);
+            }
+
+            Msg::ApplyContentFilter => {
+                self.job_filter = None;
+
+                // reload inventory automatically, but only for a valid filter:
+                if self.compile_filter() {
+                    push_history(&mut self.data.filter_history, &self.data.filter_content.clone());
+                    self.store_state();
+                    self.job_onload = self.autoload_inventory();
+                }
+            }
+
+            Msg::SelectFilterHistory(ChangeData::Select(select)) => {
+                // first option is a placeholder:
+                let filter
+                    = select
+                        .selected_index()
+                        .and_then(|index| (index as usize).checked_sub(1))
+                        .and_then(|index| self.data.filter_history.get(index).cloned());
+                if let Some(filter) = filter {
+                    if let Some(mut task) = self.job_filter.take() {
+                        task.cancel();
+                    }
+                    self.snapshot();
+                    self.data.filter_content = filter;
+                    self.store_state();
+                    self.link.send_self(Msg::ApplyContentFilter);
+                }
+            }
+
+            Msg::SelectFilterHistory(_) => {}
+
+            Msg::SetInventoryUrl(url) => {
+                self.data.inventory_url = url.trim().to_string();
+                self.store_state();
+                self.trace(&format!("SetInventoryUrl: {}", self.data.inventory_url));
+
+                // reload inventory automatically:
+                self.job_onload = self.autoload_inventory();
+            }
+
+            Msg::SetAuthToken(token) => {
+                self.auth_token = token.trim().to_string();
+
+                // reload inventory automatically:
+                self.job_onload = self.autoload_inventory();
+            }
+
+            Msg::SetWebSocketUrl(url) => {
+                self.data.websocket_url = url.trim().to_string();
+                self.store_state();
+                self.trace(&format!("SetWebSocketUrl: {}", self.data.websocket_url));
+            }
+
+            Msg::SetDeployNote(note) => {
+                self.data.deploy_note = note;
+                self.store_state();
+            }
+
+            Msg::SetCommandTemplate(template) => {
+                self.data.command_template = template;
+                self.store_state();
+            }
+
+            Msg::SetDeployInterval(value) => {
+                if let Some(interval) = parse_interval_ms(&value) {
+                    self.data.deploy_interval_ms = interval;
+                    self.store_state();
+                    self.trace(&format!("SetDeployInterval: {}ms", self.data.deploy_interval_ms));
+                }
+            }
+
+            Msg::SetMaxParallel(value) => {
+                if let Some(max_parallel) = value.trim().parse::<usize>().ok().filter(|max| *max > 0) {
+                    self.data.max_parallel = max_parallel.min(PARALLEL_MAX);
+                    self.store_state();
+                    self.trace(&format!("SetMaxParallel: {}", self.data.max_parallel));
+                }
+            }
+
+            Msg::SetHostTimeout(value) => {
+                if let Ok(timeout) = value.trim().parse::<u64>() {
+                    self.data.host_timeout_ms = timeout;
+                    self.store_state();
+                    self.trace(&format!("SetHostTimeout: {}ms", self.data.host_timeout_ms));
+                }
+            }
+
+            Msg::SetLimit(value) => {
+                // empty or invalid value clears limit:
+                self.data.limit = value.trim().parse::<usize>().ok().filter(|limit| *limit > 0);
+                self.store_state();
+                self.trace(&format!("SetLimit: {:?}", self.data.limit));
+            }
+
+            Msg::SetInventoryPoll(value) => {
+                if let Some(interval) = parse_interval_ms(&value) {
+                    self.data.inventory_poll_ms = interval;
+                    self.store_state();
+                    self.trace(&format!("SetInventoryPoll: {}ms", self.data.inventory_poll_ms));
+                }
+            }
+
+            Msg::SetOrUnsetHost(data) => {
+                match data {
+                    ChangeData::Select(hosts) => {
+                        // picked hosts hidden by display filter stay picked:
+                        let selected = hosts.selected_values();
+                        self.data.hosts_picked
+                            = self
+                                .data
+                                .hosts_all
+                                .iter()
+                                .filter(|host| {
+                                    selected.contains(host)
+                                    || (!self.data.is_host_shown(host) && self.data.hosts_picked.contains(host))
+                                })
+                                .cloned()
+                                .collect();
+                        self.store_state();
+                        self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+                    }
+
+                    ChangeData::Value(host) => {
+                        // single value events toggle given host:
+                        if let Some(index) = self.data.hosts_picked.iter().position(|picked| *picked == host) {
+                            self.data.hosts_picked.remove(index);
+                        } else if self.data.hosts_all.contains(&host) {
+                            self.data.hosts_picked.push(host);
+                        }
+                        self.store_state();
+                        self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+                    }
+
+                    ChangeData::Files(files) => {
+                        self.trace(&format!("NoOp for ChangeData::Files(_): {:?}", files));
+                    }
+                }
+            }
+
+            Msg::SelectAllHosts => {
+                self.data.hosts_picked = self.data.hosts_all.clone();
+                self.store_state();
+                self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+            }
+
+            Msg::PinPickedHosts => {
+                for host in self.data.hosts_picked.clone() {
+                    if !self.data.pinned_hosts.contains(&host) {
+                        self.data.pinned_hosts.push(host);
+                    }
+                }
+                self.store_state();
+            }
+
+            Msg::UnpinHost(host) => {
+                self.data.pinned_hosts.retain(|pinned| *pinned != host);
+                self.store_state();
+            }
+
+            Msg::SelectPinnedHosts => {
+                // pinned hosts absent from inventory can't be deployed:
+                self.data.hosts_picked
+                    = self
+                        .data
+                        .pinned_hosts
+                        .iter()
+                        .filter(|host| self.data.hosts_all.contains(host))
+                        .cloned()
+                        .collect();
+                let absent = self.data.pinned_hosts.len() - self.data.hosts_picked.len();
+                if absent > 0 {
+                    self.notify(format!("{} pinned hosts absent from inventory", absent));
+                }
+                self.store_state();
+                self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+            }
+
+            Msg::SelectGroup(ChangeData::Select(select)) => {
+                // first option is a placeholder:
+                let group
+                    = select
+                        .selected_index()
+                        .and_then(|index| (index as usize).checked_sub(1))
+                        .and_then(|index| self.data.group_names().get(index).cloned());
+                if let Some(group) = group {
+                    self.data.hosts_picked
+                        = self
+                            .data
+                            .hosts_groups
+                            .get(&group)
+                            .cloned()
+                            .unwrap_or_default();
+                    self.store_state();
+                    self.trace(&format!("Group: {} Hosts Selected: {}", group, self.data.hosts_picked.len()));
+                }
+            }
+
+            Msg::SelectGroup(_) => {}
+
+            Msg::SelectTags(ChangeData::Select(select)) => {
+                self.snapshot();
+                self.data.tags_picked = select.selected_values();
+                self.store_state();
+                self.trace(&format!("SelectTags: {:?}", self.data.tags_picked));
+
+                // reload inventory narrowed to picked tags:
+                self.job_onload = self.autoload_inventory();
+            }
+
+            Msg::SelectTags(_) => {}
+
+            Msg::SetBulkPaste(text) => {
+                self.bulk_paste = text;
+                return false;
+            }
+
+            Msg::ApplyBulkPaste => {
+                let pasted = parse_host_list(&self.bulk_paste);
+                if pasted.is_empty() {
+                    return false;
+                }
+                self.snapshot();
+                self.data.hosts_picked
+                    = self
+                        .data
+                        .hosts_all
+                        .iter()
+                        .filter(|host| pasted.contains(host))
+                        .cloned()
+                        .collect();
+                let mut missing: Vec<String> = Vec::new();
+                for host in pasted.iter().filter(|host| !self.data.hosts_all.contains(host)) {
+                    if !missing.contains(host) {
+                        missing.push(host.clone());
+                    }
+                }
+                if !missing.is_empty() {
+                    let message = format!("Pasted hosts not in inventory: {}", missing.join(", "));
+                    self.console.warn(&message);
+                    self.push_message(Level::Warn, message);
+                }
+                self.store_state();
+                self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+            }
+
+            Msg::DeselectAllHosts => {
+                self.snapshot();
+                self.data.hosts_picked.clear();
+                self.store_state();
+                self.trace(&format!("Hosts Selected: {}", self.data.hosts_picked.len()));
+            }
+
+            Msg::PruneUnreachable(hosts) => {
+                let dropped: Vec<String>
+                    = hosts
+                        .into_iter()
+                        .filter(|host| self.data.hosts_picked.contains(host))
+                        .collect();
+                if dropped.is_empty() {
+                    return false;
+                }
+                // keep deploy position pointing at the same next host:
+                let started
+                    = self
+                        .data
+                        .hosts_picked
+                        .iter()
+                        .take(self.deploy_step)
+                        .filter(|host| dropped.contains(host))
+                        .count();
+                self.deploy_step = self.deploy_step.saturating_sub(started);
+                self.data.hosts_picked.retain(|host| !dropped.contains(host));
+                for host in &dropped {
+                    self.data.hosts_status.insert(host.clone(), DeployStatus::Failed("unreachable".to_string()));
+                }
+                self.push_message(Level::Warn, format!("Dropped unreachable hosts: {}", dropped.join(", ")));
+                self.store_state();
+            }
+
+            Msg::SetEnvironment(ChangeData::Value(environment)) => {
+                let environment: String
+                    = environment
+                        .trim()
+                        .chars()
+                        .filter(|character| character.is_ascii_alphanumeric() || *character == '-' || *character == '_')
+                        .collect();
+                if environment == self.environment {
+                    return true;
+                }
+                if self.deploying {
+                    self.notify("Can't switch environment while deploying".to_string());
+                    return true;
+                }
+                // keep changes of environment being left, then switch to state of picked one:
+                if self.dirty {
+                    self.store_state();
+                }
+                self.environment = environment;
+                storage_insert(&Area::Local, ENVIRONMENT_BROWSER_ID, &self.environment);
+                self.trace(&format!("SetEnvironment: {}", self.environment));
+
+                // environment without stored state starts from defaults, with its inventory loaded fresh:
+                if !self.restore_state() {
+                    self.data = CenDashData::default();
+                    self.dirty = false;
+                    self.compile_filter();
+                    self.job_onload = self.autoload_inventory();
+                }
+            }
+
+            Msg::SetEnvironment(_) => {}
+
+            Msg::ToggleStorageArea => {
+                // state moves to picked area, nothing is left behind in the other one:
+                let key = self.storage_key();
+                self.storage().remove(&key);
+                self.storage_area
+                    = match self.storage_area {
+                        Area::Local => Area::Session,
+                        Area::Session => Area::Local,
+                    };
+                storage_insert(&Area::Local, STORAGE_AREA_BROWSER_ID, storage_area_name(&self.storage_area));
+                self.store_state();
+                self.trace(&format!("ToggleStorageArea: {}", storage_area_name(&self.storage_area)));
+            }
+
+            Msg::ToggleOnlyFailed => {
+                self.data.only_failed = !self.data.only_failed;
+                self.store_state();
+            }
+
+            Msg::TogglePickedOnly => {
+                self.data.picked_only = !self.data.picked_only;
+                self.store_state();
+            }
+
+            Msg::ClearMessages => {
+                self.data.messages.clear();
+                self.store_state();
+            }
+
+            Msg::CopyPickedHosts => {
+                if self.data.hosts_picked.is_empty() {
+                    self.notify("No hosts selected".to_string());
+                } else {
+                    copy_to_clipboard(&self.data.hosts_picked.join("\n"));
+                    self.notify(format!("Copied {} hosts to clipboard", self.data.hosts_picked.len()));
+                }
+            }
+
+            Msg::CopyDeployCommand => {
+                if !is_valid_gitref(&self.data.gitref) {
+                    self.notify("Enter valid GitRef first".to_string());
+                } else if self.data.hosts_picked.is_empty() {
+                    self.notify("No hosts selected".to_string());
+                } else {
+                    copy_to_clipboard(&self.deploy_command());
+                    self.notify("Copied deploy command to clipboard".to_string());
+                }
+            }
+
+            Msg::ToggleDarkMode => {
+                self.data.dark_mode = !self.data.dark_mode;
+                self.store_state();
+            }
+
+            Msg::ToggleVerboseConsole => {
+                self.data.log_level
+                    = match self.data.log_level {
+                        Level::Info => Level::Warn,
+                        _ => Level::Info,
+                    };
+                self.store_state();
+            }
+
+            Msg::CycleHostSort => {
+                self.data.host_sort = self.data.host_sort.next();
+                self.store_state();
+            }
+
+            Msg::Undo => {
+                if self.deploying {
+                    self.notify("Can't undo while deploying".to_string());
+                    return true;
+                }
+                if let Some(mut data) = self.undo_snapshot.take() {
+                    // deploys done since snapshot stay recorded:
+                    data.deploy_history = std::mem::take(&mut self.data.deploy_history);
+                    data.last_deploy_summary = self.data.last_deploy_summary.take();
+                    data.last_deployed_hosts = std::mem::take(&mut self.data.last_deployed_hosts);
+                    let filter_changed
+                        = data.filter_content != self.data.filter_content
+                        || data.exclude_content != self.data.exclude_content
+                        || data.filter_case_insensitive != self.data.filter_case_insensitive
+                        || data.filter_anchored != self.data.filter_anchored;
+                    self.data = data;
+                    self.compile_filter();
+                    self.store_state();
+                    self.notify("Undone last change".to_string());
+
+                    // reload inventory matching restored filter:
+                    if filter_changed {
+                        self.job_onload = self.autoload_inventory();
+                    }
+                }
+            }
+
+            Msg::StoreData => {
+                self.store_state();
+                self.notify("Stored state data".to_string());
+            }
+
+            Msg::RestoreData => {
+                if self.dirty && !self.dialog.confirm("Discard unsaved changes and restore stored state?") {
+                    return false;
+                }
+                self.restore_state();
+            }
+
+            Msg::ResetState => {
+                if self.deploying {
+                    self.notify("Can't reset while deploying".to_string());
+                    return true;
+                }
+                if !self.dialog.confirm("Reset all settings, history and messages to defaults?") {
+                    return false;
+                }
+                self.snapshot();
+                self.data = CenDashData::default();
+                let key = self.storage_key();
+                self.storage().remove(&key);
+                self.compile_filter();
+                self.notify("State reset to defaults".to_string());
+
+                // reload inventory fresh, picking all hosts:
+                self.job_onload = self.autoload_inventory();
+            }
+
+            Msg::ToggleDeployHistory => {
+                self.deploy_history_visible = !self.deploy_history_visible;
+            }
+
+            Msg::ExportDeployHistory => {
+                download_file("cendash-deploy-history.csv", "text/csv", &self.data.deploy_history_csv());
+                self.trace(&format!("Exported {} deploy history entries", self.data.deploy_history.len()));
+                return false;
+            }
+
+            Msg::ExportHostsCsv => {
+                download_file("cendash-hosts.csv", "text/csv", &self.data.hosts_csv());
+                self.trace(&format!("Exported {} hosts", self.data.hosts_all.len()));
+                return false;
+            }
+
+            Msg::ExportState => {
+                match serde_json::to_string_pretty(&self.data) {
+                    Ok(json) => {
+                        download_file("cendash-state.json", "application/json", &json);
+                        self.trace("Exported app state");
+                    }
+
+                    Err(error) => {
+                        let message = format!("Failed to export state: {}", error);
+                        self.console.error(&message);
+                        self.push_message(Level::Error, message);
+                    }
+                }
+            }
+
+            Msg::ImportState(ChangeData::Files(files)) => {
+                if let Some(file) = files.iter().next() {
+                    let callback = self.link.send_back(Msg::ImportStateLoaded);
+                    let task = self.reader.read_file(file, callback);
+                    self.job_import = Some(Box::new(task));
+                }
+            }
+
+            Msg::ImportState(_) => {}
+
+            Msg::ImportStateLoaded(file) => {
+                self.job_import = None;
+                // older or unversioned exports are migrated like stored state:
+                match serde_json::from_slice::<serde_json::Value>(&file.content) {
+                    Ok(stored) if stored.is_object() => {
+                        if self.dirty && !self.dialog.confirm("Discard unsaved changes and import state file?") {
+                            return false;
+                        }
+                        let (data, from_version, dropped) = migrate_state(stored);
+                        if from_version != SCHEMA_VERSION {
+                            self.trace(&format!("Migrated imported state from schema version: {} to: {}", from_version, SCHEMA_VERSION));
+                        }
+                        if !dropped.is_empty() {
+                            let message = format!("Dropped unreadable imported fields: {}", dropped.join(", "));
+                            self.console.warn(&message);
+                            self.push_message(Level::Warn, message);
+                        }
+                        self.snapshot();
+                        self.data = data;
+                        self.compile_filter();
+                        self.store_state();
+                        self.trace(&format!("Imported app state from: {}", file.name));
+                    }
+
+                    Ok(_) => {
+                        let message = format!("Invalid state file: {}: not a JSON object", file.name);
+                        self.console.error(&message);
+                        self.push_message(Level::Error, message);
+                    }
+
+                    Err(error) => {
+                        let message = format!("Invalid state file: {}: {}", file.name, error);
+                        self.console.error(&message);
+                        self.push_message(Level::Error, message);
+                    }
+                }
+            }
+
+        }
+        true
+    }
+}
@@ -0,0 +1,1195 @@
+use stdweb::traits::{IEvent, IKeyboardEvent};
+use yew::{html, ChangeData, Html, Renderable};
+
+use crate::model::{DeployStatus, DeploySummary, Level, LogEntry, Model, Msg};
+use crate::{
+    apply_theme, format_duration, format_time, is_valid_gitref, scroll_to_bottom, storage_area_name,
+    validity_class, validity_style, COMMAND_TEMPLATE, HOSTS_SELECT_MAX_SIZE, HOSTS_SELECT_MIN_SIZE,
+    INTERVAL_MAX_MS, INTERVAL_MIN_MS, PARALLEL_MAX,
+};
+
+
+impl Model {
+
+
+    /// multi-list of inventory hosts, or loading notice while inventory is fetched:
+    fn view_hosts_select(&self) -> Html<Model> {
+        if self.inventory_loading {
+            return html! {
+                <span>
+                    { "Loading inventory…" }
+                </span>
+            };
+        }
+
+        let selected_option = |option: &String| {
+            html! {
+                <option
+                    selected=true
+                    title=self.data.host_vars_title(option)>
+                    { option }
+                </option>
+            }
+        };
+        let unselected_option = |option: &String| {
+            html! {
+                <option
+                    selected=false
+                    title=self.data.host_vars_title(option)>
+                    { option }
+                </option>
+            }
+        };
+
+        // select fits shown hosts, within sane bounds:
+        let hosts = self.data.hosts_sorted();
+        let size = hosts.len().clamp(HOSTS_SELECT_MIN_SIZE, HOSTS_SELECT_MAX_SIZE);
+        html! {
+            <select
+                name="hosts"
+                size=size
+                required=true
+                multiple=true
+                onchange=|option| Msg::SetOrUnsetHost(option)
+            >
+                { // handle selected/ unselected items on multi-list
+                    for hosts.into_iter().map(|option| {
+                        if self.data.hosts_picked.contains(option) {
+                            selected_option(option)
+                        } else {
+                            unselected_option(option)
+                        }
+                    })
+                }
+            </select>
+        }
+    }
+
+
+    /// tag filter, shown when inventory hosts carry tags:
+    fn view_tags_select(&self) -> Html<Model> {
+        let tags = self.data.tag_names();
+        if tags.is_empty() {
+            return html! {};
+        }
+        html! {
+            <pre>
+                <label>
+                    { "Hosts tagged with all of: " }
+                </label>
+                <select
+                    name="tags"
+                    multiple=true
+                    size=tags.len().min(HOSTS_SELECT_MIN_SIZE)
+                    onchange=|option| Msg::SelectTags(option)
+                >
+                    { for tags.iter().map(|tag| {
+                        html! {
+                            <option selected=self.data.tags_picked.contains(tag)>
+                                { tag }
+                            </option>
+                        }
+                    })}
+                </select>
+            </pre>
+        }
+    }
+
+
+    /// pasted list of hosts to pick, one per line or comma separated:
+    fn view_bulk_paste(&self) -> Html<Model> {
+        html! {
+            <pre>
+                <label>
+                    { "Paste hosts: " }
+                </label>
+                <br/>
+                <textarea
+                    name="bulk_paste"
+                    rows="4"
+                    cols="48"
+                    placeholder="One host per line, or comma separated"
+                    value=&self.bulk_paste
+                    oninput=|element| Msg::SetBulkPaste(element.value)
+                />
+                <br/>
+                <button
+                    disabled=self.bulk_paste.trim().is_empty()
+                    onclick=|_| Msg::ApplyBulkPaste>{ "Apply" }
+                </button>
+            </pre>
+        }
+    }
+
+
+    /// pinned hosts with unpin buttons, absent hosts are grayed out:
+    fn view_pinned_hosts(&self) -> Html<Model> {
+        let view_pinned = |host: &String| {
+            let pinned = host.clone();
+            let deployed = host.clone();
+            let present = self.data.hosts_all.contains(host);
+            let color
+                = if present {
+                    "inherit"
+                } else {
+                    "gray"
+                };
+            html! {
+                <span style=format!("color: {};", color)>
+                    { host }
+                    <button
+                        title="Deploy to this host only"
+                        disabled=self.deploying || !present
+                        onclick=|_| Msg::DeploySingle(deployed.clone())>{ "▶" }
+                    </button>
+                    <button
+                        title="Unpin"
+                        onclick=|_| Msg::UnpinHost(pinned.clone())>{ "×" }
+                    </button>
+                    { "  " }
+                </span>
+            }
+        };
+        html! {
+            <pre>
+                <label>
+                    { "Pinned hosts: " }
+                </label>
+                { for self.data.pinned_hosts.iter().map(view_pinned) }
+                <br/>
+                <button
+                    disabled=self.data.hosts_picked.is_empty()
+                    onclick=|_| Msg::PinPickedHosts>{ "Pin-Selected" }
+                </button>
+                { "  " }
+                <button
+                    disabled=self.data.pinned_hosts.is_empty()
+                    onclick=|_| Msg::SelectPinnedHosts>{ "Select-Pinned" }
+                </button>
+            </pre>
+        }
+    }
+
+
+    /// dot colored by outcome of last inventory fetch:
+    fn view_backend_health(&self) -> Html<Model> {
+        let (color, title)
+            = match self.backend_healthy {
+                Some(true) => ("green", "Inventory reachable"),
+                Some(false) => ("red", "Inventory unreachable"),
+                None => ("gray", "Inventory not fetched yet"),
+            };
+        html! {
+            <span title=title style=format!("color: {};", color)>
+                { " ●" }
+            </span>
+        }
+    }
+
+
+    /// inventory URL with its bearer token and connection test:
+    fn view_inventory_url(&self) -> Html<Model> {
+        html! {
+            <pre>
+                <label>
+                    { "Inventory URL: " }
+                </label>
+                <input
+                    name="inventory_url"
+                    size="32"
+                    placeholder="Inventory URL"
+                    value=&self.data.inventory_url
+                    oninput=|element| Msg::SetInventoryUrl(element.value)
+                />
+                { "  " }
+                <button
+                    disabled=self.job_ping.is_some()
+                    onclick=|_| Msg::Ping>{ "Test-Connection" }
+                </button>
+                { "  " }
+                <input
+                    name="auth_token"
+                    type="password"
+                    size="24"
+                    placeholder="Bearer token (not stored)"
+                    value=&self.auth_token
+                    oninput=|element| Msg::SetAuthToken(element.value)
+                />
+            </pre>
+        }
+    }
+
+
+    /// active environment, keeping its own stored state:
+    fn view_environment(&self) -> Html<Model> {
+        html! {
+            <pre>
+                <label>
+                    { "Environment: " }
+                </label>
+                <strong style="color: orange;">
+                    { if self.environment.is_empty() { "default" } else { &self.environment } }
+                </strong>
+                { "  " }
+                <input
+                    name="environment"
+                    size="12"
+                    placeholder="like: staging"
+                    value=&self.environment
+                    onchange=|value| Msg::SetEnvironment(value)
+                />
+            </pre>
+        }
+    }
+
+
+    /// floating banner of transient notifications:
+    fn view_notifications(&self) -> Html<Model> {
+        let view_notification = |(id, message): &(u64, String)| {
+            let id = *id;
+            html! {
+                <p
+                    style="margin: 0.2em; padding: 0.5em; color: black; background: lightyellow; border: 1px solid gray; cursor: pointer;"
+                    onclick=|_| Msg::DismissNotification(id)>
+                    { message }
+                </p>
+            }
+        };
+        html! {
+            <aside style="position: fixed; top: 1em; left: 50%; transform: translateX(-50%);">
+                { for self.notifications.iter().map(view_notification) }
+            </aside>
+        }
+    }
+
+
+    /// outcome of previous deploy, also from earlier sessions:
+    fn view_last_deploy(&self) -> Html<Model> {
+        match &self.data.last_deploy_summary {
+            Some(summary) if !self.deploying => html! {
+                <pre>
+                    { summary.to_string() }
+                </pre>
+            },
+            _ => html! {},
+        }
+    }
+
+
+    /// deploy progress bar, hidden when there's nothing to deploy:
+    fn view_progress(&self) -> Html<Model> {
+        let (completed, total) = self.data.deploy_progress();
+        if total == 0 || self.data.hosts_status.is_empty() {
+            return html! {};
+        }
+        let percent = completed * 100 / total;
+
+        // NOTE: yew sets `value` on input elements only, so <progress> can't be driven here:
+        html! {
+            <pre>
+                <div style="display: inline-block; width: 12em; height: 0.8em; border: 1px solid gray;">
+                    <div style=format!("width: {}%; height: 100%; background: green;", percent)></div>
+                </div>
+                { format!("  {}/{} ({}%), in flight: {}", completed, total, percent, self.data.in_flight()) }
+                { // refreshed by each deploy step
+                    match self.deploy_elapsed_ms() {
+                        Some(elapsed) => format!(", Elapsed: {}", format_duration(elapsed)),
+                        None => String::new(),
+                    }
+                }
+            </pre>
+        }
+    }
+
+
+    /// jump button shown when new messages arrived while scrolled up:
+    fn view_new_messages(&self) -> Html<Model> {
+        if self.scrolled_to_bottom || self.data.messages.len() == self.messages_seen {
+            return html! {};
+        }
+        html! {
+            <button
+                style="position: fixed; bottom: 1em; left: 50%;"
+                onclick=|_| Msg::ScrollToBottom>{ "New messages ↓" }
+            </button>
+        }
+    }
+
+
+    /// inventory reload keeping filter and host selection, and hard reload picking all hosts:
+    fn view_reload_buttons(&self) -> Html<Model> {
+        html! {
+            <>
+                <button
+                    disabled=self.inventory_loading
+                    onclick=|_| Msg::InventoryLoad>{ "Reload-Inventory" }
+                </button>
+                { "  " }
+                <button
+                    disabled=self.inventory_loading
+                    title="Reload inventory and select all its hosts"
+                    onclick=|_| Msg::HardReloadInventory>{ "Hard-Reload" }
+                </button>
+            </>
+        }
+    }
+
+
+    /// dry-run and fast mode deploy options:
+    fn view_deploy_options(&self) -> Html<Model> {
+        html! {
+            <>
+                { "  " }
+                <label>
+                    <input
+                        type="checkbox"
+                        checked=self.data.dry_run
+                        onclick=|_| Msg::ToggleDryRun
+                    />
+                    { "Dry-run" }
+                </label>
+                { "  " }
+                <label title="Deploy without confirmation">
+                    <input
+                        type="checkbox"
+                        checked=self.data.skip_confirm
+                        onclick=|_| Msg::ToggleSkipConfirm
+                    />
+                    { "Fast mode" }
+                </label>
+            </>
+        }
+    }
+
+
+    /// collapsible panel of hosts grouped by name prefix, with group pick checkboxes:
+    fn view_prefix_groups(&self) -> Html<Model> {
+        let focused_host
+            = self
+                .focused_index
+                .and_then(|index| self.data.prefix_group_hosts(&self.prefix_groups_expanded).get(index).cloned());
+        let view_host = |host: &String| {
+            let mark = if self.data.hosts_picked.contains(host) { "[x]" } else { "[ ]" };
+            let style = if focused_host.as_ref() == Some(host) { "outline: 1px dotted;" } else { "" };
+            let deployed = host.clone();
+            html! {
+                <div style=style>
+                    { format!("{} {} ", mark, host) }
+                    <button
+                        title="Deploy to this host only"
+                        disabled=self.deploying
+                        onclick=|_| Msg::DeploySingle(deployed.clone())>{ "▶" }
+                    </button>
+                </div>
+            }
+        };
+        let view_group = |(prefix, hosts): (String, Vec<String>)| {
+            let expanded = self.prefix_groups_expanded.contains(&prefix);
+            let picked = hosts.iter().all(|host| self.data.hosts_picked.contains(host));
+            let expand = prefix.clone();
+            let pick = prefix.clone();
+            html! {
+                <div>
+                    <input
+                        type="checkbox"
+                        checked=picked
+                        onclick=|_| Msg::TogglePrefixGroupPicked(pick.clone())
+                    />
+                    <button
+                        onclick=|_| Msg::TogglePrefixGroupExpanded(expand.clone())>{ if expanded { "▾" } else { "▸" } }
+                    </button>
+                    { format!(" {} ({})", prefix, hosts.len()) }
+                    {
+                        if expanded {
+                            html! {
+                                <div style="padding-left: 2em;">
+                                    { for hosts.iter().map(view_host) }
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </div>
+            }
+        };
+        html! {
+            <div>
+                <pre>
+                    <button
+                        onclick=|_| Msg::TogglePrefixGroups>{ if self.prefix_groups_visible { "Hide-Prefix-Groups" } else { "Show-Prefix-Groups" } }
+                    </button>
+                </pre>
+                {
+                    if self.prefix_groups_visible {
+                        // arrows, Home/ End and typed characters move focus over expanded hosts, space picks focused host:
+                        html! {
+                            <pre
+                                tabindex="0"
+                                onkeydown=|event| {
+                                    let key = event.key();
+                                    if ["ArrowDown", "ArrowUp", "Home", "End", " "].contains(&key.as_str()) {
+                                        event.prevent_default(); // keep page from scrolling
+                                    }
+                                    Msg::PrefixGroupsKey(key)
+                                }>
+                                { for self.data.prefix_groups().into_iter().map(view_group) }
+                            </pre>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+
+    /// collapsible panel with inventory as served by the backend:
+    fn view_raw_inventory(&self) -> Html<Model> {
+        html! {
+            <div>
+                <pre>
+                    <button
+                        onclick=|_| Msg::ToggleRawInventory>{ if self.inventory_raw_visible { "Hide-Raw-Inventory" } else { "Show-Raw-Inventory" } }
+                    </button>
+                    { format!("  ({} bytes)", self.data.inventory_raw.len()) }
+                </pre>
+                {
+                    if self.inventory_raw_visible {
+                        html! {
+                            <pre class="panel inventory-raw" style="border: 1px dashed gray; padding: 0.5em;">
+                                { &self.data.inventory_raw }
+                            </pre>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+
+    /// deploy log lines of host picked in status list:
+    fn view_host_log(&self) -> Html<Model> {
+        let host
+            = match &self.host_log_shown {
+                Some(host) => host,
+                None => return html! {},
+            };
+        let lines = self.data.hosts_logs.get(host).map(Vec::as_slice).unwrap_or_default();
+        let shown = host.clone();
+        html! {
+            <pre class="panel logs" style="border: 1px dashed gray; padding: 0.5em;">
+                <button
+                    onclick=|_| Msg::ToggleHostLog(shown.clone())>{ "×" }
+                </button>
+                { format!("  Log of {} ({} lines)\n", host, lines.len()) }
+                { if lines.is_empty() { "No log lines prefixed with this host, see global log".to_string() } else { lines.join("\n") } }
+            </pre>
+        }
+    }
+
+
+    /// collapsible deploy log panel:
+    fn view_logs(&self) -> Html<Model> {
+        html! {
+            <div>
+                <pre>
+                    <button
+                        onclick=|_| Msg::ToggleLogs>{ if self.logs_visible { "Hide-Logs" } else { "Show-Logs" } }
+                    </button>
+                    { "  " }
+                    <button
+                        disabled=self.data.logs.is_empty()
+                        onclick=|_| Msg::CopyLogs>{ "Copy-Logs" }
+                    </button>
+                    { format!("  ({} lines)", self.data.logs.len()) }
+                </pre>
+                {
+                    if self.logs_visible {
+                        html! {
+                            <pre class="logs">
+                                { self.data.logs.join("\n") }
+                            </pre>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+
+    /// collapsible table of past deploys, most recent first:
+    fn view_deploy_history(&self) -> Html<Model> {
+        let view_summary = |summary: &DeploySummary| {
+            let style
+                = if summary.aborted || summary.failed > 0 {
+                    "color: red;"
+                } else {
+                    ""
+                };
+            html! {
+                <tr style=style>
+                    <td>{ format_time(summary.finished_at) }</td>
+                    <td>{ &summary.gitref }</td>
+                    <td>{ summary.hosts }</td>
+                    <td>{ summary.succeeded }</td>
+                    <td>{ summary.failed }</td>
+                    <td>{
+                        if summary.started_at == 0 {
+                            String::new()
+                        } else {
+                            format_duration(summary.finished_at.saturating_sub(summary.started_at))
+                        }
+                    }</td>
+                    <td>{ if summary.aborted { "aborted" } else { "" } }</td>
+                    <td>{ &summary.note }</td>
+                </tr>
+            }
+        };
+        html! {
+            <div>
+                <pre>
+                    <button
+                        onclick=|_| Msg::ToggleDeployHistory>{ if self.deploy_history_visible { "Hide-Deploy-History" } else { "Show-Deploy-History" } }
+                    </button>
+                    { "  " }
+                    <button
+                        disabled=self.data.deploy_history.is_empty()
+                        onclick=|_| Msg::ExportDeployHistory>{ "Export-CSV" }
+                    </button>
+                    { format!("  ({} deploys)", self.data.deploy_history.len()) }
+                </pre>
+                {
+                    if self.deploy_history_visible {
+                        html! {
+                            <table>
+                                <tr>
+                                    <th>{ "Finished" }</th>
+                                    <th>{ "GitRef" }</th>
+                                    <th>{ "Hosts" }</th>
+                                    <th>{ "OK" }</th>
+                                    <th>{ "Failed" }</th>
+                                    <th>{ "Took" }</th>
+                                    <th></th>
+                                    <th>{ "Note" }</th>
+                                </tr>
+                                { for self.data.deploy_history.iter().map(view_summary) }
+                            </table>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+
+    /// queue of gitrefs deployed one after another:
+    fn view_deploy_queue(&self) -> Html<Model> {
+        let view_entry = |(index, gitref): (usize, &String)| {
+            let active = self.queue_active == Some(index);
+            html! {
+                <li style=if active { "font-weight: bold;" } else { "" }>
+                    { gitref }
+                    { if active { " (active)" } else { "" } }
+                    { " " }
+                    <button
+                        disabled=self.queue_active.is_some()
+                        onclick=|_| Msg::RemoveFromQueue(index)>{ "✕" }
+                    </button>
+                </li>
+            }
+        };
+        html! {
+            <pre>
+                <button
+                    onclick=|_| Msg::QueueGitRef>{ "Queue-GitRef" }
+                </button>
+                { "  " }
+                <button
+                    disabled=self.deploying || self.data.deploy_queue.is_empty()
+                    onclick=|_| Msg::DeployQueue>{ "Deploy-Queue" }
+                </button>
+                <ol>
+                    { for self.data.deploy_queue.iter().enumerate().map(view_entry) }
+                </ol>
+            </pre>
+        }
+    }
+
+
+    /// full regex error, pointing at the position of invalid syntax:
+    fn view_filter_error(&self, error: &Option<String>) -> Html<Model> {
+        match error {
+            Some(error) => html! {
+                <div style="color: red;">
+                    { error }
+                </div>
+            },
+            None => html! {},
+        }
+    }
+
+
+    /// input of hosts exclusion filter:
+    fn view_exclude_filter(&self) -> Html<Model> {
+        html! {
+            <>
+                <br/>
+                <label>
+                    { "Exclude hosts: " }
+                </label>
+                <input
+                    name="exclude_content"
+                    class=validity_class(self.exclude_error.is_none())
+                    style=validity_style(self.exclude_error.is_none())
+                    type="find"
+                    size="32"
+                    placeholder="Exclude hosts by content"
+                    value=&self.data.exclude_content
+                    oninput=|element| Msg::SetExcludeFilter(element.value)
+                />
+                { self.view_filter_error(&self.exclude_error) }
+            </>
+        }
+    }
+
+
+    /// recently applied content filters:
+    fn view_filter_history(&self) -> Html<Model> {
+        if self.data.filter_history.is_empty() {
+            return html! {};
+        }
+        html! {
+            <select
+                name="filter_history"
+                onchange=|option| Msg::SelectFilterHistory(option)
+            >
+                <option selected=true>
+                    { "Recent filters…" }
+                </option>
+                { for self.data.filter_history.iter().map(|filter| {
+                    html! {
+                        <option>
+                            { filter }
+                        </option>
+                    }
+                })}
+            </select>
+        }
+    }
+
+
+    /// extra acknowledgement for deploy to all inventory hosts:
+    fn view_deploy_all_warning(&self) -> Html<Model> {
+        if !self.data.is_deploying_all() {
+            return html! {};
+        }
+        html! {
+            <div style="color: red;">
+                <strong>
+                    { format!("You are about to deploy to ALL {} hosts", self.data.hosts_picked.len()) }
+                </strong>
+                <br/>
+                <label>
+                    <input
+                        type="checkbox"
+                        checked=self.all_hosts_acknowledged
+                        onclick=|_| Msg::AcknowledgeAllHosts
+                    />
+                    { " I understand" }
+                </label>
+            </div>
+        }
+    }
+
+
+    /// inline confirmation panel shown before deploy starts:
+    fn view_deploy_confirm(&self) -> Html<Model> {
+        if !self.pending_confirm {
+            return html! {};
+        }
+        let deploying_all = self.data.is_deploying_all();
+        html! {
+            <pre class="panel" style="border: 1px solid orange; padding: 0.5em;">
+                { format!("Deploy GitRef: {} to {} hosts?", self.data.gitref, self.data.deploy_targets().len()) }
+                {
+                    if self.data.deploy_targets().len() < self.data.hosts_picked.len() {
+                        format!(" (limited to {} of {})", self.data.deploy_targets().len(), self.data.hosts_picked.len())
+                    } else {
+                        String::new()
+                    }
+                }
+                <br/>
+                { self.view_deploy_all_warning() }
+                <button
+                    disabled=deploying_all && !self.all_hosts_acknowledged
+                    onclick=|_| Msg::Deploy>{ "Confirm" }
+                </button>
+                { "  " }
+                <button
+                    onclick=|_| Msg::CancelDeployConfirm>{ "Cancel" }
+                </button>
+            </pre>
+        }
+    }
+
+
+}
+
+
+impl Renderable<Model> for Model {
+
+    fn view(&self) -> Html<Self> {
+        // text nodes are escaped by yew, pre-wrap keeps newlines of multi-line messages:
+        let view_message = |entry: &LogEntry| {
+            html! {
+                <p class=entry.level.class() style=format!("color: {}; white-space: pre-wrap;", entry.level.color())>
+                    { format!("[{}] {}", entry.time(), entry.message) }
+                </p>
+            }
+        };
+        let view_host_status = |(host, status): (&String, &DeployStatus)| {
+            let retried = host.clone();
+            let logged = host.clone();
+            let deployed = host.clone();
+            html! {
+                <li style=format!("color: {};", status.color())>
+                    <span
+                        title="Show deploy log of this host"
+                        style="cursor: pointer;"
+                        onclick=|_| Msg::ToggleHostLog(logged.clone())>
+                        { format!("{}: {}", host, status) }
+                    </span>
+                    <button
+                        title="Deploy to this host only"
+                        disabled=self.deploying || !self.data.hosts_all.contains(host)
+                        onclick=|_| Msg::DeploySingle(deployed.clone())>{ "▶" }
+                    </button>
+                    {
+                        if let DeployStatus::Failed(_) = status {
+                            html! {
+                                <button
+                                    title="Retry deploy to this host"
+                                    disabled=self.deploying
+                                    onclick=|_| Msg::RetryHost(retried.clone())>{ "Retry" }
+                                </button>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </li>
+            }
+        };
+        let has_job = self.job.is_some();
+
+        apply_theme(self.data.dark_mode);
+
+        // follow new contents only when user didn't scroll up to read older ones:
+        if self.scrolled_to_bottom {
+            scroll_to_bottom();
+        }
+
+        html! {
+            <article class=if self.data.dark_mode { "dark" } else { "light" }>
+                { self.view_notifications() }
+                <span style="display: block; float: left; position: fixed; top: 2em; right: 2em; background: inherit;">
+                    <label>
+                        { "Centra Deployer" }
+                    </label>
+                    { self.view_backend_health() }
+                    { self.view_environment() }
+                    <pre>
+                        <input
+                            name="gitref"
+                            class=validity_class(self.data.gitref.is_empty() || is_valid_gitref(&self.data.gitref))
+                            style=validity_style(self.data.gitref.is_empty() || is_valid_gitref(&self.data.gitref))
+                            list="gitref_history"
+                            size="42"
+                            autofocus=true
+                            required=true
+                            placeholder="Git-ref (tag, branch or sha1)"
+                            value=&self.data.gitref
+                            oninput=|element| Msg::SetGitRef(element.value)
+                        />
+                        <datalist id="gitref_history">
+                            { // option text is used as value by browsers
+                                for self.data.gitref_history.iter().map(|gitref| {
+                                    html! {
+                                        <option>
+                                            { gitref }
+                                        </option>
+                                    }
+                                })
+                            }
+                        </datalist>
+                        <br/>
+                        <input
+                            name="deploy_note"
+                            size="42"
+                            placeholder="Deploy note (like: hotfix for CEN-123)"
+                            value=&self.data.deploy_note
+                            oninput=|element| Msg::SetDeployNote(element.value)
+                        />
+                    </pre>
+                    <pre>
+                        { "Selected: " }
+                        { self.data.hosts_picked.len() }
+                        { " of: " }
+                        { self.data.hosts_all.len() }
+                        { " hosts in total."}
+                    </pre>
+                    <pre>
+                        { format!(
+                            "Inventory: {} hosts across {} groups, last loaded {}",
+                            self.data.hosts_all.len(),
+                            self.data.hosts_groups.len(),
+                            format_time(self.data.inventory_loaded_at),
+                        ) }
+                    </pre>
+                    { self.view_tags_select() }
+                    { self.view_pinned_hosts() }
+                    { self.view_bulk_paste() }
+                    { self.view_prefix_groups() }
+                    <pre>
+                        <label>
+                            { "List of hosts: " }
+                        </label>
+                        { self.view_hosts_select() }
+                    </pre>
+                    <pre>
+                        <label>
+                            { "Group: " }
+                        </label>
+                        <select
+                            name="groups"
+                            onchange=|option| Msg::SelectGroup(option)
+                        >
+                            <option selected=true>
+                                { "Pick hosts of group…" }
+                            </option>
+                            { // option label includes count of group hosts
+                                for self.data.group_names().into_iter().map(|group| {
+                                    html! {
+                                        <option>
+                                            { format!("{} ({})", group, self.data.group_size(&group)) }
+                                        </option>
+                                    }
+                                })
+                            }
+                        </select>
+                    </pre>
+                    <pre>
+                        <button
+                            onclick=|_| Msg::SelectAllHosts>{ "Select-All" }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::DeselectAllHosts>{ "Deselect-All" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=self.data.hosts_picked.is_empty()
+                            onclick=|_| Msg::CopyPickedHosts>{ "Copy-Selected" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=self.data.hosts_all.is_empty()
+                            onclick=|_| Msg::ExportHostsCsv>{ "Export-CSV" }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::CycleHostSort>{ format!("Sort: {}", self.data.host_sort) }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::TogglePickedOnly>{ if self.data.picked_only { "Show-All" } else { "Show-Picked-Only" } }
+                        </button>
+                    </pre>
+                    <pre>
+                        <label>
+                            { "Filter hosts: " }
+                        </label>
+                        <input
+                            name="filter_content"
+                            class=validity_class(self.filter_error.is_none())
+                            style=validity_style(self.filter_error.is_none())
+                            type="find"
+                            size="32"
+                            placeholder="Filter hosts by content"
+                            value=&self.data.filter_content
+                            oninput=|element| Msg::SetContentFilter(element.value)
+                        />
+                        { self.view_filter_history() }
+                        { match self.filter_preview {
+                            Some((matching, excluded, total)) => format!("  would match {} of {}, {} excluded", matching, total, excluded),
+                            None => String::new(),
+                        }}
+                        { self.view_filter_error(&self.filter_error) }
+                        { self.view_exclude_filter() }
+                        <br/>
+                        <label>
+                            <input
+                                type="checkbox"
+                                checked=self.data.filter_case_insensitive
+                                onclick=|_| Msg::ToggleFilterCaseInsensitive
+                            />
+                            { "case-insensitive" }
+                        </label>
+                        { "  " }
+                        <label>
+                            <input
+                                type="checkbox"
+                                checked=self.data.filter_anchored
+                                onclick=|_| Msg::ToggleFilterAnchored
+                            />
+                            { "whole host name" }
+                        </label>
+                    </pre>
+                    <pre>
+                        <label>
+                            { "Show hosts: " }
+                        </label>
+                        <input
+                            name="display_filter"
+                            type="find"
+                            size="32"
+                            placeholder="Show hosts containing"
+                            value=&self.data.display_filter
+                            oninput=|element| Msg::SetDisplayFilter(element.value)
+                        />
+                        { "  " }
+                        <label>
+                            <input
+                                type="checkbox"
+                                checked=self.data.display_fuzzy
+                                onclick=|_| Msg::ToggleDisplayFuzzy
+                            />
+                            { "fuzzy" }
+                        </label>
+                    </pre>
+                    { self.view_inventory_url() }
+                    <pre>
+                        <label>
+                            { "Deploy log URL: " }
+                        </label>
+                        <input
+                            name="websocket_url"
+                            size="32"
+                            placeholder="ws://host:port/path"
+                            value=&self.data.websocket_url
+                            oninput=|element| Msg::SetWebSocketUrl(element.value)
+                        />
+                    </pre>
+                    <pre>
+                        <label>
+                            { "Deploy command: " }
+                        </label>
+                        <input
+                            name="command_template"
+                            size="32"
+                            placeholder=COMMAND_TEMPLATE
+                            value=&self.data.command_template
+                            oninput=|element| Msg::SetCommandTemplate(element.value)
+                        />
+                        <br/>
+                        <input
+                            name="deploy_command"
+                            size="48"
+                            readonly=true
+                            value=self.deploy_command()
+                        />
+                        { "  " }
+                        <button
+                            disabled=self.data.gitref.is_empty() || self.data.hosts_picked.is_empty()
+                            onclick=|_| Msg::CopyDeployCommand>{ "Copy-Command" }
+                        </button>
+                    </pre>
+                    <pre>
+                        <label>
+                            { "Deploy step (ms): " }
+                        </label>
+                        <input
+                            name="deploy_interval_ms"
+                            type="number"
+                            min=INTERVAL_MIN_MS
+                            max=INTERVAL_MAX_MS
+                            value=self.data.deploy_interval_ms.to_string()
+                            onchange=|value| match value {
+                                ChangeData::Value(value) => Msg::SetDeployInterval(value),
+                                _ => Msg::SetDeployInterval(String::new()),
+                            }
+                        />
+                        { "  " }
+                        <label>
+                            { "Inventory poll (ms): " }
+                        </label>
+                        <input
+                            name="inventory_poll_ms"
+                            type="number"
+                            min=INTERVAL_MIN_MS
+                            max=INTERVAL_MAX_MS
+                            value=self.data.inventory_poll_ms.to_string()
+                            onchange=|value| match value {
+                                ChangeData::Value(value) => Msg::SetInventoryPoll(value),
+                                _ => Msg::SetInventoryPoll(String::new()),
+                            }
+                        />
+                        { "  " }
+                        <label>
+                            { "Max parallel: " }
+                        </label>
+                        <input
+                            name="max_parallel"
+                            type="number"
+                            min=1
+                            max=PARALLEL_MAX
+                            value=self.data.max_parallel.to_string()
+                            onchange=|value| match value {
+                                ChangeData::Value(value) => Msg::SetMaxParallel(value),
+                                _ => Msg::SetMaxParallel(String::new()),
+                            }
+                        />
+                        { "  " }
+                        <label>
+                            { "Host timeout (ms): " }
+                        </label>
+                        <input
+                            name="host_timeout_ms"
+                            type="number"
+                            min=0
+                            value=self.data.host_timeout_ms.to_string()
+                            onchange=|value| match value {
+                                ChangeData::Value(value) => Msg::SetHostTimeout(value),
+                                _ => Msg::SetHostTimeout(String::new()),
+                            }
+                        />
+                        { "  " }
+                        <label>
+                            { "Limit to first: " }
+                        </label>
+                        <input
+                            name="limit"
+                            type="number"
+                            min=1
+                            placeholder="all"
+                            value=self.data.limit.map(|limit| limit.to_string()).unwrap_or_default()
+                            oninput=|element| Msg::SetLimit(element.value)
+                        />
+                    </pre>
+                    <pre>
+                        <button
+                            disabled=self.deploying || self.undo_snapshot.is_none()
+                            onclick=|_| Msg::Undo>{ "Undo" }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::StoreData>{ "Store-State" }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::RestoreData>{ "Restore-State" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=self.deploying
+                            onclick=|_| Msg::ResetState>{ "Reset-State" }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::ExportState>{ "Export-State" }
+                        </button>
+                        { "  " }
+                        <label>
+                            { "Import-State: " }
+                        </label>
+                        <input
+                            type="file"
+                            accept=".json,application/json"
+                            onchange=|files| Msg::ImportState(files)
+                        />
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::ToggleStorageArea>{ format!("Storage: {}", storage_area_name(&self.storage_area)) }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::ToggleDarkMode>{ if self.data.dark_mode { "Light-Mode" } else { "Dark-Mode" } }
+                        </button>
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::ToggleVerboseConsole>{ if self.data.log_level == Level::Info { "Console: verbose" } else { "Console: quiet" } }
+                        </button>
+                    </pre>
+                    <pre>
+                        <button
+                            disabled=has_job || self.pending_confirm || self.data.hosts_picked.is_empty()
+                            onclick=|_| Msg::RequestDeployConfirm>{ "Deploy!" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=!has_job
+                            onclick=|_| Msg::Abort>{ "Abort!" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=has_job || self.data.failed_hosts().is_empty()
+                            onclick=|_| Msg::RetryFailed>{ "Retry-Failed" }
+                        </button>
+                        { "  " }
+                        <button
+                            disabled=has_job || self.data.last_deployed_hosts.is_empty()
+                            title="Deploy picked hosts not covered by last deploy"
+                            onclick=|_| Msg::DeployNewHosts>{ "Deploy-New-Hosts" }
+                        </button>
+                        { self.view_deploy_options() }
+                    </pre>
+                    { self.view_deploy_confirm() }
+                    { self.view_deploy_queue() }
+                    { self.view_progress() }
+                    { self.view_last_deploy() }
+                    <pre>
+                        { self.view_reload_buttons() }
+                        { "  " }
+                        <button
+                            onclick=|_| Msg::ClearMessages>{ "Clear-Logs" }
+                        </button>
+                        { "  " }
+                        <label>
+                            <input
+                                type="checkbox"
+                                checked=self.data.only_failed
+                                onclick=|_| Msg::ToggleOnlyFailed
+                            />
+                            { "only failed" }
+                        </label>
+                    </pre>
+                </span>
+
+                <content>
+                    <ul>
+                        { // render deploy status in order of picked hosts
+                            for self.data.hosts_picked.iter().filter_map(|host| {
+                                self.data.hosts_status.get(host).map(|status| (host, status))
+                            }).map(view_host_status)
+                        }
+                    </ul>
+                    { self.view_host_log() }
+                    { for self.data.messages.iter().filter(|entry| !self.data.only_failed || entry.level == Level::Error).map(view_message) }
+                    { self.view_raw_inventory() }
+                    { self.view_logs() }
+                    { self.view_deploy_history() }
+                    { self.view_new_messages() }
+                </content>
+            </article>
+        }
+    }
+}
+